@@ -0,0 +1,188 @@
+/// Horizontal direction of a held key
+#[derive(Clone, Copy, PartialEq)]
+pub enum ShiftDirection {
+    Left,
+    Right
+}
+
+/// Repeats the moves of held keys at the engine rate rather than at the
+/// keyboard one : Delayed Auto Shift (DAS) before the first repeat, then
+/// Auto Repeat Rate (ARR) between repeats. Also accelerates the gravity by
+/// the soft drop factor while the down key is held.
+///
+/// Only enabled when the terminal reports key releases : otherwise held
+/// keys could never be released, so every key press is a single move.
+pub struct AutoRepeat {
+    enabled: bool,
+    das: u128,
+    arr: u128,
+    soft_drop_factor: u64,
+    left_held: bool,
+    right_held: bool,
+    soft_drop_held: bool,
+    direction: Option<ShiftDirection>,
+    held_frames: u128
+}
+
+/// Number of cells to shift when the ARR is zero : straight to the wall
+pub const SHIFT_TO_WALL: u32 = u32::MAX;
+
+impl AutoRepeat {
+    pub fn new(das: u128, arr: u128, soft_drop_factor: u64) -> AutoRepeat {
+        AutoRepeat {
+            enabled: false,
+            das,
+            arr,
+            soft_drop_factor,
+            left_held: false,
+            right_held: false,
+            soft_drop_held: false,
+            direction: None,
+            held_frames: 0
+        }
+    }
+
+    pub fn set_enabled_flag(&mut self, value: bool) {
+        self.enabled = value
+    }
+
+    pub fn is_soft_drop_held(&self) -> bool {
+        self.soft_drop_held
+    }
+
+    pub fn get_soft_drop_factor(&self) -> u64 {
+        self.soft_drop_factor
+    }
+
+    /// The last pressed direction wins, and charges its DAS from scratch
+    pub fn press_shift(&mut self, direction: ShiftDirection) {
+        if !self.enabled {
+            return;
+        }
+
+        match direction {
+            ShiftDirection::Left => self.left_held = true,
+            ShiftDirection::Right => self.right_held = true
+        }
+
+        self.direction = Some(direction);
+        self.held_frames = 0;
+    }
+
+    /// Releasing the active direction falls back on the other one if it
+    /// is still held
+    pub fn release_shift(&mut self, direction: ShiftDirection) {
+        match direction {
+            ShiftDirection::Left => self.left_held = false,
+            ShiftDirection::Right => self.right_held = false
+        }
+
+        if self.direction == Some(direction) {
+            self.held_frames = 0;
+            self.direction = match direction {
+                ShiftDirection::Left if self.right_held => Some(ShiftDirection::Right),
+                ShiftDirection::Right if self.left_held => Some(ShiftDirection::Left),
+                _ => None
+            };
+        }
+    }
+
+    pub fn set_soft_drop_held_flag(&mut self, value: bool) {
+        self.soft_drop_held = self.enabled && value
+    }
+
+    /// Counts one more frame of the held direction.
+    /// Returns the direction and the number of cells to shift this frame.
+    pub fn tick(&mut self) -> Option<(ShiftDirection, u32)> {
+        let direction = self.direction?;
+        self.held_frames += 1;
+
+        if self.held_frames < self.das {
+            return None;
+        }
+
+        if self.arr == 0 {
+            return Some((direction, SHIFT_TO_WALL));
+        }
+
+        if (self.held_frames - self.das).is_multiple_of(self.arr) {
+            Some((direction, 1))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn held(direction: ShiftDirection, das: u128, arr: u128) -> AutoRepeat {
+        let mut auto_repeat = AutoRepeat::new(das, arr, 20);
+        auto_repeat.set_enabled_flag(true);
+        auto_repeat.press_shift(direction);
+
+        auto_repeat
+    }
+
+    /// Returns the frames, counted from 1, on which a shift happens
+    fn shift_frames(auto_repeat: &mut AutoRepeat, frames: u128, expected: (ShiftDirection, u32)) -> Vec<u128> {
+        let mut shifts = Vec::new();
+
+        for frame in 1..=frames {
+            if let Some(shift) = auto_repeat.tick() {
+                assert!(shift == expected, "Unexpected shift on frame {frame}");
+                shifts.push(frame);
+            }
+        }
+
+        shifts
+    }
+
+    #[test]
+    fn das_charges_before_the_first_repeat() {
+        let mut auto_repeat = held(ShiftDirection::Left, 5, 2);
+
+        assert_eq!(shift_frames(&mut auto_repeat, 10, (ShiftDirection::Left, 1)), vec![5, 7, 9]);
+    }
+
+    #[test]
+    fn zero_arr_shifts_to_the_wall_every_frame() {
+        let mut auto_repeat = held(ShiftDirection::Right, 3, 0);
+
+        assert_eq!(shift_frames(&mut auto_repeat, 5, (ShiftDirection::Right, SHIFT_TO_WALL)), vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn last_pressed_direction_wins_and_charges_again() {
+        let mut auto_repeat = held(ShiftDirection::Left, 5, 2);
+        shift_frames(&mut auto_repeat, 8, (ShiftDirection::Left, 1));
+
+        auto_repeat.press_shift(ShiftDirection::Right);
+        assert_eq!(shift_frames(&mut auto_repeat, 6, (ShiftDirection::Right, 1)), vec![5]);
+
+        auto_repeat.release_shift(ShiftDirection::Right);
+        assert_eq!(shift_frames(&mut auto_repeat, 6, (ShiftDirection::Left, 1)), vec![5]);
+
+        auto_repeat.release_shift(ShiftDirection::Left);
+        assert!(auto_repeat.tick().is_none());
+    }
+
+    #[test]
+    fn releasing_the_other_direction_keeps_the_charge() {
+        let mut auto_repeat = held(ShiftDirection::Left, 5, 2);
+        auto_repeat.press_shift(ShiftDirection::Right);
+        shift_frames(&mut auto_repeat, 4, (ShiftDirection::Right, 1));
+
+        auto_repeat.release_shift(ShiftDirection::Left);
+        assert_eq!(shift_frames(&mut auto_repeat, 3, (ShiftDirection::Right, 1)), vec![1, 3]);
+    }
+
+    #[test]
+    fn keys_do_not_repeat_without_key_releases() {
+        let mut auto_repeat = AutoRepeat::new(5, 2, 20);
+        auto_repeat.press_shift(ShiftDirection::Left);
+
+        assert!((0..10).all(|_| auto_repeat.tick().is_none()));
+    }
+}
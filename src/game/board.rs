@@ -0,0 +1,140 @@
+use crate::game::cell::Cell;
+use crate::game::state::{GridCoords, PivotCoords};
+
+pub const MIN_WIDTH: usize = 4;
+pub const MAX_WIDTH: usize = 30;
+pub const MIN_VISIBLE_HEIGHT: usize = 4;
+pub const MAX_VISIBLE_HEIGHT: usize = 50;
+
+/// Grid of cells sized at launch. Rows are stored top to bottom : the
+/// vanish zone first, hidden above the visible field, then the visible
+/// rows.
+pub struct Board {
+    width: usize,
+    visible_height: usize,
+    vanish_zone_height: usize,
+    cells: Vec<Cell>,
+    /// Clock at which each cell was filled, kept in step with `cells`
+    lock_clocks: Vec<u128>
+}
+
+impl Board {
+    /// Creates an empty board with a vanish zone as high as the visible
+    /// field, as in the guideline
+    pub fn new(width: usize, visible_height: usize) -> Board {
+        let vanish_zone_height = visible_height;
+
+        Board {
+            width,
+            visible_height,
+            vanish_zone_height,
+            cells: vec![Cell::Empty; width * (vanish_zone_height + visible_height)],
+            lock_clocks: vec![0; width * (vanish_zone_height + visible_height)]
+        }
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the number of rows, vanish zone included
+    pub fn get_height(&self) -> usize {
+        self.vanish_zone_height + self.visible_height
+    }
+
+    pub fn get_visible_height(&self) -> usize {
+        self.visible_height
+    }
+
+    pub fn get_vanish_zone_height(&self) -> usize {
+        self.vanish_zone_height
+    }
+
+    pub fn get_cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    pub fn get_lock_clocks(&self) -> &[u128] {
+        &self.lock_clocks
+    }
+
+    /// Tetrominos spawn in the two rows right above the visible field,
+    /// centered and rounded to the left
+    pub fn get_spawn_position(&self) -> PivotCoords {
+        PivotCoords {
+            i: self.vanish_zone_height as i32 - 1,
+            j: (self.width as i32 - 1) / 2
+        }
+    }
+
+    /// Checks if the coordinates are inside the board borders. Coordinates
+    /// wrapped around from negative values are never inside.
+    pub fn contains(&self, coords: GridCoords) -> bool {
+        coords.i < self.get_height() && coords.j < self.width
+    }
+
+    pub fn to_index(&self, coords: GridCoords) -> usize {
+        coords.i * self.width + coords.j
+    }
+
+    pub fn get_cell(&self, i: usize, j: usize) -> Cell {
+        self.cells[i * self.width + j]
+    }
+
+    pub fn get_cell_at(&self, coords: GridCoords) -> Cell {
+        self.get_cell(coords.i, coords.j)
+    }
+
+    /// Fills a cell, remembering the clock at which it locked
+    pub fn lock_cell_at(&mut self, coords: GridCoords, cell: Cell, clock: u128) {
+        let index = self.to_index(coords);
+
+        self.cells[index] = cell;
+        self.lock_clocks[index] = clock;
+    }
+
+    pub fn is_line_full(&self, i: usize) -> bool {
+        (0..self.width).all(|j| self.get_cell(i, j) != Cell::Empty)
+    }
+
+    pub fn is_line_empty(&self, i: usize) -> bool {
+        (0..self.width).all(|j| self.get_cell(i, j) == Cell::Empty)
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.fill(Cell::Empty);
+        self.lock_clocks.fill(0);
+    }
+
+    /// Adds a row at the bottom, filled by `cell_at` column by column at
+    /// `clock`, and shifts every row up. The top row is lost.
+    pub fn push_line_up(&mut self, cell_at: impl Fn(usize) -> Cell, clock: u128) {
+        let bottom_row_origin = self.cells.len() - self.width;
+
+        self.cells.copy_within(self.width.., 0);
+        self.lock_clocks.copy_within(self.width.., 0);
+
+        for j in 0..self.width {
+            self.cells[bottom_row_origin + j] = cell_at(j);
+            self.lock_clocks[bottom_row_origin + j] = clock;
+        }
+    }
+
+    /// Returns the number of rows holding garbage cells
+    pub fn count_garbage_lines(&self) -> usize {
+        self.cells
+            .chunks(self.width)
+            .filter(|row| row.contains(&Cell::Garbage))
+            .count()
+    }
+
+    /// Removes row `i`, shifting every row above it one row down
+    pub fn remove_line(&mut self, i: usize) {
+        let row_origin = i * self.width;
+
+        self.cells.copy_within(0..row_origin, self.width);
+        self.cells[..self.width].fill(Cell::Empty);
+        self.lock_clocks.copy_within(0..row_origin, self.width);
+        self.lock_clocks[..self.width].fill(0);
+    }
+}
@@ -1,6 +1,11 @@
+use crate::game::tetromino::Tetromino;
+
 #[derive(Clone, Copy)]
 #[derive(PartialEq)]
 pub enum Cell {
-    Full,
+    /// Cell of a locked piece, which keeps its color
+    Full(Tetromino),
+    /// Cell of a garbage row pushed in from the bottom
+    Garbage,
     Empty
 }
\ No newline at end of file
@@ -1,135 +1,373 @@
 
-use std::cmp::Ordering;
+use crate::game::{auto_repeat::ShiftDirection, cell::Cell, gravity, tetromino::RotationDirection, top_out::TopOut};
+use crate::game::phase::Phase;
+use crate::game::scoring::{ClearReport, RotationKick, TSpin};
+use crate::game::state::{CurrentTetromino, GridCoords, State};
 
-use crate::game::{self, cell::Cell, state::State};
-
-const EARNED_POINTS_SINGLE: u32 = 10;
-const EARNED_POINTS_DOUBLE: u32 = 30;
-const EARNED_POINTS_TRIPLE: u32 = 50;
-const EARNED_POINTS_TETRIS: u32 = 80;
+const EARNED_POINTS_SOFT_DROP_ROW: u32 = 1;
+const EARNED_POINTS_HARD_DROP_ROW: u32 = 2;
 
 impl State {
-    pub fn move_current_tetromino_left(&mut self) {
+    /// Moves the current tetromino one column to the left if nothing is in
+    /// the way. Returns true if it moved.
+    pub fn move_current_tetromino_left(&mut self) -> bool {
         let collisions = self
             .get_current_tetromino_ref()
-            .get_left_collisions_cell_indexes();
+            .get_left_collisions_cell_indexes(self.get_board(), self.get_rotation_system());
 
         if  collisions.contains(&None) ||
             self.is_collision_with_other_tetromino(collisions) {
 
-            return
+            return false
         }
 
         self.get_current_tetromino_mutref()
         .move_left();
+        self.get_lock_delay_mutref().on_tetromino_moved();
+        self.set_last_rotation_kick(None);
+
+        true
     }
 
-    pub fn move_current_tetromino_right(&mut self) {
+    /// Moves the current tetromino one column to the right if nothing is in
+    /// the way. Returns true if it moved.
+    pub fn move_current_tetromino_right(&mut self) -> bool {
         let collisions = self
             .get_current_tetromino_ref()
-            .get_right_collisions_cell_indexes();
+            .get_right_collisions_cell_indexes(self.get_board(), self.get_rotation_system());
 
         if collisions.contains(&None)  ||
             self.is_collision_with_other_tetromino(collisions) {
 
-            return
+            return false
         }
 
         self.get_current_tetromino_mutref()
         .move_right();
+        self.get_lock_delay_mutref().on_tetromino_moved();
+        self.set_last_rotation_kick(None);
+
+        true
     }
 
-    pub fn move_current_tetromino_down(&mut self) {
-        let collisions = self
-            .get_current_tetromino_ref()
-            .get_down_collisions_cell_indexes();
+    /// Shifts the current tetromino by the given number of cells, or until
+    /// it hits something
+    pub fn auto_shift_current_tetromino(&mut self, direction: ShiftDirection, cells: u32) {
+        for _ in 0..cells {
+            let moved = match direction {
+                ShiftDirection::Left => self.move_current_tetromino_left(),
+                ShiftDirection::Right => self.move_current_tetromino_right()
+            };
 
-        if collisions.contains(&None) {
-            self.stick_current_tetromino();
-            return;
+            if !moved {
+                break;
+            }
+        }
+    }
+
+    /// Moves the current tetromino one row down if nothing is below it.
+    /// Returns true if it moved.
+    pub fn move_current_tetromino_down(&mut self) -> bool {
+        if !self.can_current_tetromino_move_down() {
+            return false;
         }
 
-        if self.is_collision_with_other_tetromino(collisions) {
-            self.stick_current_tetromino();
-        } else {
-            self.get_current_tetromino_mutref()
-            .move_down();
+        self.get_current_tetromino_mutref()
+        .move_down();
+
+        let row = self.get_current_tetromino_ref().get_position().i;
+        self.get_lock_delay_mutref().on_tetromino_fell(row);
+        self.set_last_rotation_kick(None);
+
+        true
+    }
+
+    /// Moves the current tetromino one row down on player request,
+    /// earning points for the row
+    pub fn soft_drop_current_tetromino(&mut self) {
+        if self.move_current_tetromino_down() {
+            self.add_to_score(EARNED_POINTS_SOFT_DROP_ROW);
         }
     }
 
-    fn is_collision_with_other_tetromino(&mut self, collisions: Vec<Option<usize>>) -> bool {
-        for collision in collisions {
-            if let Some(collision_index) = collision {
-                if self.grid[collision_index] == Cell::Full {
-                    return true;
+    /// Moves the current tetromino down by the cells the gravity of the
+    /// current level accumulated during this frame. From 20G on, the
+    /// tetromino goes straight to its landing row.
+    /// While the soft drop key is held, the gravity is multiplied by the
+    /// soft drop factor and each row fallen earns points.
+    pub fn apply_gravity(&mut self) {
+        let mut gravity = self.get_gravity_curve().get_gravity(self.get_level());
+        let soft_drop_held = self.get_auto_repeat_ref().is_soft_drop_held();
+
+        if soft_drop_held {
+            gravity = gravity.saturating_mul(self.get_auto_repeat_ref().get_soft_drop_factor());
+        }
+
+        let mut fallen_rows = 0;
+
+        if gravity >= gravity::MAX_GRAVITY {
+            while self.move_current_tetromino_down() {
+                fallen_rows += 1;
+            }
+        } else {
+            let mut progress = self.get_gravity_progress() + gravity::per_game_frame(gravity);
+
+            while progress >= gravity::G {
+                if !self.move_current_tetromino_down() {
+                    progress = 0;
+                    break;
                 }
+
+                fallen_rows += 1;
+                progress -= gravity::G;
             }
+
+            self.set_gravity_progress(progress);
         }
 
-        false
+        if soft_drop_held {
+            self.add_to_score(EARNED_POINTS_SOFT_DROP_ROW * fallen_rows);
+        }
     }
 
-    pub fn rotate_current_tetromino(&mut self) {
-        self.increment_rotation();
-        
-        // Verify that the rotated tetromino doesn't clip in the grid border
-        match self
-                .get_current_tetromino_ref()
-                .is_tetromino_clipping_horizontaly() {
-            
-            Ordering::Less => self.move_current_tetromino_right(),
-            Ordering::Greater => self.move_current_tetromino_left(),
-            Ordering::Equal => ()
+    /// Locks the current tetromino once it spent the whole lock delay
+    /// touching down
+    pub fn update_lock_delay(&mut self) {
+        if self.can_current_tetromino_move_down() {
+            self.get_lock_delay_mutref().cancel();
+        } else if self.get_lock_delay_mutref().tick_grounded() {
+            self.stick_current_tetromino();
         }
+    }
 
-        if self
-            .get_current_tetromino_ref()
-            .is_tetromino_clipping_vertically() {
+    /// Drops the current tetromino to its landing row and locks it
+    /// immediately, earning points for each row dropped
+    pub fn hard_drop_current_tetromino(&mut self) {
+        let ghost_tetromino = self.get_ghost_tetromino();
+        let dropped_rows = ghost_tetromino.get_position().i
+            - self.get_current_tetromino_ref().get_position().i;
 
-            self
-                .get_current_tetromino_mutref()
-                .move_up();
+        *self.get_current_tetromino_mutref() = ghost_tetromino;
+
+        if dropped_rows > 0 {
+            self.set_last_rotation_kick(None);
+        }
+
+        self.add_to_score(EARNED_POINTS_HARD_DROP_ROW * dropped_rows as u32);
+        self.stick_current_tetromino();
+    }
+
+    /// Returns the current tetromino moved down to the row where it would
+    /// land
+    pub fn get_ghost_tetromino(&self) -> CurrentTetromino {
+        let mut ghost_tetromino = *self.get_current_tetromino_ref();
+
+        while self.can_tetromino_move_down(&ghost_tetromino) {
+            ghost_tetromino.move_down();
+        }
+
+        ghost_tetromino
+    }
+
+    pub fn can_current_tetromino_move_down(&self) -> bool {
+        self.can_tetromino_move_down(self.get_current_tetromino_ref())
+    }
+
+    fn can_tetromino_move_down(&self, tetromino: &CurrentTetromino) -> bool {
+        let collisions = tetromino.get_down_collisions_cell_indexes(self.get_board(), self.get_rotation_system());
+
+        !collisions.contains(&None) &&
+        !self.is_collision_with_other_tetromino(collisions)
+    }
+
+    fn is_collision_with_other_tetromino(&self, collisions: Vec<Option<usize>>) -> bool {
+        for collision_index in collisions.into_iter().flatten() {
+            if self.get_board().get_cells()[collision_index] != Cell::Empty {
+                return true;
+            }
         }
 
+        false
+    }
+
+    /// Rotates the current tetromino, testing the kicks of the rotation
+    /// system in order. Nothing happens if no kick fits.
+    pub fn rotate_current_tetromino(&mut self, direction: RotationDirection) {
+        let current_tetromino = *self.get_current_tetromino_ref();
+        let old_rotation = current_tetromino.get_rotation();
+        let new_rotation = direction.apply_to(old_rotation);
+        let pivot = current_tetromino.get_position();
+        let board = self.get_board();
+
+        let is_blocked = |(i, j): (i32, i32)| {
+            let coords = GridCoords { i: (pivot.i + i) as usize, j: (pivot.j + j) as usize };
+
+            !board.contains(coords) || board.get_cell_at(coords) != Cell::Empty
+        };
+
+        let kicks = self.get_rotation_system().get_kicks(
+            current_tetromino.get_tetromino(),
+            old_rotation,
+            new_rotation,
+            &is_blocked
+        );
+
+        for (kick_index, kick) in kicks.into_iter().enumerate() {
+            let rotated_tetromino = current_tetromino.rotated(new_rotation, kick);
+
+            if self.does_tetromino_fit(&rotated_tetromino) {
+                *self.get_current_tetromino_mutref() = rotated_tetromino;
+                self.get_lock_delay_mutref().on_tetromino_moved();
+                self.set_last_rotation_kick(Some(RotationKick { direction, index: kick_index }));
+                return;
+            }
+        }
+    }
+
+    /// Checks if the tetromino is inside the grid without overlapping
+    /// full cells
+    pub fn does_tetromino_fit(&self, tetromino: &CurrentTetromino) -> bool {
+        if !tetromino.is_inside_board(self.get_board(), self.get_rotation_system()) {
+            return false;
+        }
+
+        let cells_coords = tetromino.get_cells_coords(self.get_rotation_system());
+
+        cells_coords
+            .iter()
+            .all(|cell_coords| self.get_board().get_cell_at(*cell_coords) == Cell::Empty)
     }
 
     fn stick_current_tetromino(&mut self) {
-        let old_tetromino_ref = self.get_current_tetromino_ref();
-        let cells_coords = old_tetromino_ref.get_cells_coords();
+        let spin = self.detect_current_tetromino_spin();
+        let cells_coords = self.get_current_tetromino_ref().get_cells_coords(self.get_rotation_system());
+        let tetromino = self.get_current_tetromino_ref().get_tetromino();
+        let clock = self.get_clock();
 
-        self.grid[cells_coords.0.to_grid_index()] = Cell::Full;
-        self.grid[cells_coords.1.to_grid_index()] = Cell::Full;
-        self.grid[cells_coords.2.to_grid_index()] = Cell::Full;
-        self.grid[cells_coords.3.to_grid_index()] = Cell::Full;
+        let board = self.get_board_mutref();
 
+        for cell_coords in cells_coords {
+            board.lock_cell_at(cell_coords, Cell::Full(tetromino), clock);
+        }
+        self.increment_pieces();
+
+        if self.is_current_tetromino_locked_out() {
+            self.set_top_out(TopOut::LockOut);
+
+            if self.is_game_over() {
+                return;
+            }
+        }
+
+        let cleared_lines = self.score_full_lines(spin);
+
+        if cleared_lines == 0 {
+            self.start_entry_delay();
+        } else if self.get_line_clear_delay() == 0 {
+            self.remove_full_lines();
+            self.start_entry_delay();
+        } else {
+            self.set_phase(Phase::LineClear(self.get_line_clear_delay()));
+        }
+    }
+
+    /// Counts down the line clear and entry delays, removing the full lines
+    /// and spawning the next tetromino when they are over
+    pub fn update_phase(&mut self) {
+        match self.get_phase() {
+            Phase::Falling => (),
+            Phase::LineClear(frames) if frames <= 1 => {
+                self.remove_full_lines();
+                self.start_entry_delay();
+            },
+            Phase::LineClear(frames) => self.set_phase(Phase::LineClear(frames - 1)),
+            Phase::Entry(frames) if frames <= 1 => self.spawn_next_tetromino(),
+            Phase::Entry(frames) => self.set_phase(Phase::Entry(frames - 1))
+        }
+    }
+
+    fn start_entry_delay(&mut self) {
+        if self.get_entry_delay() == 0 {
+            self.spawn_next_tetromino();
+        } else {
+            self.set_phase(Phase::Entry(self.get_entry_delay()));
+        }
+    }
+
+    /// Spawns the next tetromino, then holds and rotates it if the keys
+    /// are held (IHS and IRS)
+    fn spawn_next_tetromino(&mut self) {
         self.set_next_tetromino_to_current();
+        self.level_up_on_spawn();
+
+        let (hold, rotation) = self.get_initial_actions_mutref().take();
+
+        if hold && !self.is_game_over() {
+            self.swap_current_stored_tetrominos();
+        }
+
+        if let Some(direction) = rotation && !self.is_game_over() {
+            self.rotate_current_tetromino(direction);
+        }
     }
 
-    pub fn clear_grid_lines_full(&mut self) {
+    /// Scores the full lines left by the tetromino that just locked, along
+    /// with its spin, back-to-back and combo bonuses.
+    /// Returns the number of full lines.
+    fn score_full_lines(&mut self, spin: TSpin) -> u32 {
         let old_lines_counter = self.get_lines();
+        let board = self.get_board();
+        let cleared_lines = (0..board.get_height())
+            .filter(|i| board.is_line_full(*i))
+            .count() as u32;
 
-        for i in 0..game::GRID_HEIGHT {
-            if self.is_grid_line_full(i) {
-                self.clear_grid_line(i);
-                self.shift_tetromino_cells_down(i);
+        if cleared_lines == 0 {
+            self.set_combo(None);
+
+            if spin == TSpin::None {
+                self.set_last_clear(None);
+                return 0;
             }
         }
 
-        let new_lines_counter = self.get_lines();
+        let mut report = ClearReport {
+            lines: cleared_lines,
+            spin,
+            back_to_back: false,
+            combo: 0,
+            perfect_clear: cleared_lines > 0 && self.is_perfect_clear()
+        };
+
+        if cleared_lines > 0 {
+            report.combo = self.get_combo().map_or(0, |combo| combo + 1);
+            self.set_combo(Some(report.combo));
 
-        match new_lines_counter - old_lines_counter {
-            1 => self.add_to_score(EARNED_POINTS_SINGLE * self.get_level()),
-            2 => self.add_to_score(EARNED_POINTS_DOUBLE * self.get_level()),
-            3 => self.add_to_score(EARNED_POINTS_TRIPLE * self.get_level()),
-            4 => self.add_to_score(EARNED_POINTS_TETRIS * self.get_level()),
-            _ => ()
+            report.back_to_back = report.is_difficult() && self.is_back_to_back_ready();
+            self.set_back_to_back_flag(report.is_difficult());
         }
 
-        if old_lines_counter / 10 < new_lines_counter / 10 {
-            self.increment_level();
+        self.add_to_score(self.get_mode().get_clear_points(&report, self.get_level()));
+        self.set_last_clear(Some(report));
+        self.add_to_lines(cleared_lines);
+        self.level_up_on_clear(old_lines_counter, cleared_lines);
+
+        cleared_lines
+    }
+
+    /// Removes the full lines, shifting the cells above them down
+    fn remove_full_lines(&mut self) {
+        let board = self.get_board_mutref();
+
+        for i in 0..board.get_height() {
+            if board.is_line_full(i) {
+                board.remove_line(i);
+            }
         }
     }
 
+    /// Swaps the current tetromino with the held one, or with the next one
+    /// if none is held. Under the guideline rule, the tetromino coming out
+    /// cannot be swapped again until it locks.
     pub fn swap_current_stored_tetrominos(&mut self) {
         if self.can_store() {
 
@@ -138,10 +376,120 @@ impl State {
             
             if let Some(tetromino) = old_stored {
                 self.set_new_current_tetromino(tetromino);
-                self.set_can_store_flag(false);
             } else {
                 self.set_next_tetromino_to_current();
             }
+
+            self.set_can_store_flag(false);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::game::cell::Cell;
+    use crate::game::piece_set;
+    use crate::game::rotation_system::RotationSystemKind;
+    use crate::game::settings::Settings;
+    use crate::game::state::{GridCoords, State};
+    use crate::game::tetromino::RotationDirection;
+
+    fn state_with(name: &str) -> State {
+        state_with_rotation(name, RotationSystemKind::Srs)
+    }
+
+    fn state_with_rotation(name: &str, rotation_system: RotationSystemKind) -> State {
+        let settings = Settings { rotation_system, ..Settings::default() };
+        let mut state = State::new(&settings, piece_set::load("tetromino").unwrap()).unwrap();
+        let tetromino = state.get_piece_set().find(name).unwrap();
+        state.set_new_current_tetromino(tetromino);
+
+        state
+    }
+
+    /// Fills the cell at offset (i, j) from the pivot of the current
+    /// tetromino
+    fn lock_garbage_near_pivot(state: &mut State, (i, j): (i32, i32)) {
+        let pivot = state.get_current_tetromino_ref().get_position();
+        let coords = GridCoords { i: (pivot.i + i) as usize, j: (pivot.j + j) as usize };
+
+        state.get_board_mutref().lock_cell_at(coords, Cell::Garbage, 0);
+    }
+
+    #[test]
+    fn rotation_kicks_off_the_left_wall() {
+        let mut state = state_with("J");
+
+        // J in state R against the left wall : its pivot is on column 0
+        let current = *state.get_current_tetromino_ref();
+        let position = current.get_position();
+        *state.get_current_tetromino_mutref() = current.rotated(1, (2, -position.j));
+
+        state.rotate_current_tetromino(RotationDirection::Clockwise);
+
+        let rotated = state.get_current_tetromino_ref();
+        assert_eq!(rotated.get_rotation(), 2);
+        assert_eq!(rotated.get_position().j, 1);
+        assert_eq!(state.get_last_rotation_kick().map(|kick| kick.index), Some(1));
+    }
+
+    #[test]
+    fn rotation_fails_cleanly_when_no_kick_fits() {
+        let mut state = state_with("T");
+        let current = *state.get_current_tetromino_ref();
+        let cells_coords = current.get_cells_coords(state.get_rotation_system());
+        let board = state.get_board_mutref();
+
+        for i in 0..board.get_height() {
+            for j in 0..board.get_width() {
+                let coords = GridCoords { i, j };
+
+                if !cells_coords.iter().any(|cell_coords| cell_coords.equals(&coords)) {
+                    board.lock_cell_at(coords, Cell::Garbage, 0);
+                }
+            }
+        }
+
+        for direction in [
+            RotationDirection::Clockwise,
+            RotationDirection::Counterclockwise,
+            RotationDirection::Half
+        ] {
+            state.rotate_current_tetromino(direction);
+
+            let unchanged = state.get_current_tetromino_ref();
+            assert_eq!(unchanged.get_rotation(), 0);
+            assert_eq!(unchanged.get_position().i, current.get_position().i);
+            assert_eq!(unchanged.get_position().j, current.get_position().j);
+            assert!(state.get_last_rotation_kick().is_none());
+        }
+    }
+
+    #[test]
+    fn ars_kicks_right_when_blocked_on_the_left() {
+        let mut state = state_with_rotation("T", RotationSystemKind::Ars);
+        let column = state.get_current_tetromino_ref().get_position().j;
+
+        // The upside down T needs (1, -1), which is full
+        lock_garbage_near_pivot(&mut state, (1, -1));
+        state.rotate_current_tetromino(RotationDirection::Half);
+
+        let rotated = state.get_current_tetromino_ref();
+        assert_eq!(rotated.get_rotation(), 2);
+        assert_eq!(rotated.get_position().j, column + 1);
+    }
+
+    #[test]
+    fn ars_does_not_kick_t_blocked_in_the_center_column() {
+        let mut state = state_with_rotation("T", RotationSystemKind::Ars);
+        let current = *state.get_current_tetromino_ref();
+
+        // State R needs (-1, 0), above the pivot : a kick right would fit
+        lock_garbage_near_pivot(&mut state, (-1, 0));
+        state.rotate_current_tetromino(RotationDirection::Clockwise);
+
+        let unchanged = state.get_current_tetromino_ref();
+        assert_eq!(unchanged.get_rotation(), 0);
+        assert_eq!(unchanged.get_position().j, current.get_position().j);
+    }
+}
@@ -1,32 +1,98 @@
+#[allow(clippy::module_inception)]
 mod core;
 
-use crate::game::{game_action::GameAction, state::State};
-
-const LINES_FULL_CHECKING_TIME: u128 = 10;
-const DEFAULT_TETROMINO_FALLING_TIME: u128 = 80;
-const DELTA_FALLING_TIME: u128 = 5;
+use crate::game::{auto_repeat::ShiftDirection, game_action::GameAction, state::State, tetromino::RotationDirection};
 
 pub fn perform_action(state: &mut State, action: GameAction) {
+    if !state.is_tetromino_falling() {
+        return;
+    }
+
     match action {
-        GameAction::Left => state.move_current_tetromino_left(),
-        GameAction::Right => state.move_current_tetromino_right(),
-        GameAction::Down => state.move_current_tetromino_down(),
-        GameAction::Rotate => state.rotate_current_tetromino(),
+        GameAction::Left => { state.move_current_tetromino_left(); },
+        GameAction::Right => { state.move_current_tetromino_right(); },
+        GameAction::Down => state.soft_drop_current_tetromino(),
+        GameAction::HardDrop => state.hard_drop_current_tetromino(),
+        GameAction::RotateClockwise | GameAction::RotateCounterclockwise | GameAction::Rotate180 =>
+            state.rotate_current_tetromino(get_rotation_direction(action)),
         GameAction::Store => state.swap_current_stored_tetrominos(),
         _ => ()
     }
 }
 
+/// Performs the action of a pressed key, and starts auto-repeating it if
+/// it is a shift or a soft drop. Keys keep charging their DAS between
+/// two tetrominos, and rotation and hold keys apply to the next spawn.
+pub fn press_action(state: &mut State, action: GameAction) {
+    let falling = state.is_tetromino_falling();
+
+    match action {
+        GameAction::Left =>
+            state.get_auto_repeat_mutref().press_shift(ShiftDirection::Left),
+        GameAction::Right =>
+            state.get_auto_repeat_mutref().press_shift(ShiftDirection::Right),
+        GameAction::Down =>
+            state.get_auto_repeat_mutref().set_soft_drop_held_flag(true),
+        GameAction::RotateClockwise | GameAction::RotateCounterclockwise | GameAction::Rotate180 =>
+            state.get_initial_actions_mutref().press_rotation(get_rotation_direction(action), falling),
+        GameAction::Store =>
+            state.get_initial_actions_mutref().press_hold(falling),
+        _ => ()
+    }
+
+    perform_action(state, action);
+}
+
+/// Stops auto-repeating the action of a released key
+pub fn release_action(state: &mut State, action: GameAction) {
+    match action {
+        GameAction::Left =>
+            state.get_auto_repeat_mutref().release_shift(ShiftDirection::Left),
+        GameAction::Right =>
+            state.get_auto_repeat_mutref().release_shift(ShiftDirection::Right),
+        GameAction::Down =>
+            state.get_auto_repeat_mutref().set_soft_drop_held_flag(false),
+        GameAction::RotateClockwise | GameAction::RotateCounterclockwise | GameAction::Rotate180 =>
+            state.get_initial_actions_mutref().release_rotation(get_rotation_direction(action)),
+        GameAction::Store =>
+            state.get_initial_actions_mutref().release_hold(),
+        _ => ()
+    }
+}
+
+fn get_rotation_direction(action: GameAction) -> RotationDirection {
+    match action {
+        GameAction::RotateCounterclockwise => RotationDirection::Counterclockwise,
+        GameAction::Rotate180 => RotationDirection::Half,
+        _ => RotationDirection::Clockwise
+    }
+}
+
 pub fn increment_clock_and_trigger_events(state: &mut State) {
     state.increment_clock();
 
-    if state.get_clock() %
-        (DEFAULT_TETROMINO_FALLING_TIME - state.get_level() as u128 * DELTA_FALLING_TIME) == 0 {
+    let auto_shift = state.get_auto_repeat_mutref().tick();
 
-        state.move_current_tetromino_down();
+    if !state.is_tetromino_falling() {
+        state.update_phase();
     }
 
-    if state.get_clock() % LINES_FULL_CHECKING_TIME == 0 {
-        state.clear_grid_lines_full();
+    // A tetromino that just spawned falls during the same frame, so that it
+    // appears directly on the stack at 20G
+    if state.is_tetromino_falling() && !state.is_game_over() {
+        if let Some((direction, cells)) = auto_shift {
+            state.auto_shift_current_tetromino(direction, cells);
+        }
+
+        state.apply_gravity();
+        state.update_lock_delay();
+    }
+
+    if let Some((rows, holes)) = state.get_mode().get_scheduled_garbage(state) {
+        state.add_garbage_rows(rows, holes);
     }
-}
\ No newline at end of file
+
+    if state.get_mode().is_goal_reached(state) {
+        state.set_goal_reached_flag();
+    }
+}
@@ -1,9 +1,21 @@
+#[derive(Clone, Copy, PartialEq)]
 pub enum GameAction {
     Left,
     Right,
     Down,
-    Rotate,
+    HardDrop,
+    RotateClockwise,
+    RotateCounterclockwise,
+    Rotate180,
     Store,
+    ToggleGhost,
     Pause,
     Quit
-}
\ No newline at end of file
+}
+
+/// Key event read from the terminal, mapped to the action of the key.
+/// Releases are only reported by terminals supporting them.
+pub enum InputEvent {
+    Pressed(GameAction),
+    Released(GameAction)
+}
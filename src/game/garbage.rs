@@ -0,0 +1,200 @@
+use rand::Rng;
+
+use crate::game::cell::Cell;
+use crate::game::state::State;
+use crate::game::top_out::TopOut;
+
+/// Where the holes of the garbage rows go
+#[derive(Clone, Copy)]
+pub enum GarbageHoles {
+    /// Every row has its hole in the given column
+    Column(usize),
+    /// Every row has its hole in a column drawn at random
+    Random,
+    /// Each row has the given percentage of chance to move its hole away
+    /// from the column of the previous row
+    Messy(u32)
+}
+
+impl GarbageHoles {
+    /// Parses `random`, `column:<index>` or `messy:<percentage>`
+    pub fn from_name(name: &str) -> Result<GarbageHoles, String> {
+        let error = || format!("Unknown garbage holes '{name}', expected random, column:<index> or messy:<percentage>");
+
+        if name == "random" {
+            return Ok(GarbageHoles::Random);
+        }
+
+        if let Some(column) = name.strip_prefix("column:") {
+            return column.parse().map(GarbageHoles::Column).map_err(|_| error());
+        }
+
+        match name.strip_prefix("messy:").map(|percentage| percentage.parse()) {
+            Some(Ok(percentage)) if percentage <= 100 => Ok(GarbageHoles::Messy(percentage)),
+            _ => Err(error())
+        }
+    }
+}
+
+impl State {
+    /// Pushes `count` garbage rows in from the bottom of the board, shifting
+    /// the stack and the current tetromino up. Tops the game out if the
+    /// rows push blocks of the visible field into the vanish zone.
+    pub fn add_garbage_rows(&mut self, count: usize, holes: GarbageHoles) {
+        if count == 0 {
+            return;
+        }
+
+        let board = self.get_board();
+        let vanish_zone_height = board.get_vanish_zone_height();
+        let overflows = (vanish_zone_height..board.get_height())
+            .take(count)
+            .any(|i| !board.is_line_empty(i));
+
+        for _ in 0..count {
+            let hole = self.draw_garbage_hole(holes);
+            let clock = self.get_clock();
+
+            self.get_board_mutref().push_line_up(
+                |j| if j == hole { Cell::Empty } else { Cell::Garbage },
+                clock
+            );
+            self.set_last_garbage_hole(Some(hole));
+        }
+
+        if self.is_tetromino_falling() {
+            self.push_current_tetromino_out_of_stack(count);
+        }
+
+        if overflows {
+            self.set_top_out(TopOut::Overflow);
+        }
+    }
+
+    fn draw_garbage_hole(&mut self, holes: GarbageHoles) -> usize {
+        let width = self.get_board().get_width();
+        let last_hole = self.get_last_garbage_hole();
+
+        match (holes, last_hole) {
+            (GarbageHoles::Column(column), _) => column.min(width - 1),
+            (GarbageHoles::Messy(percentage), Some(last_hole))
+                if !self.get_garbage_rng_mutref().random_ratio(percentage, 100) => last_hole,
+            (GarbageHoles::Messy(_), Some(last_hole)) => {
+                // Any column but the previous one
+                let hole = self.get_garbage_rng_mutref().random_range(0..width - 1);
+                if hole >= last_hole { hole + 1 } else { hole }
+            },
+            _ => self.get_garbage_rng_mutref().random_range(0..width)
+        }
+    }
+
+    /// Moves the current tetromino up, at most `max_rows` rows, until it
+    /// no longer overlaps the stack
+    fn push_current_tetromino_out_of_stack(&mut self, max_rows: usize) {
+        for _ in 0..max_rows {
+            if self.does_tetromino_fit(self.get_current_tetromino_ref()) {
+                return;
+            }
+
+            self.get_current_tetromino_mutref().move_up();
+        }
+
+        if !self.does_tetromino_fit(self.get_current_tetromino_ref()) {
+            self.set_top_out(TopOut::Overflow);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::piece_set;
+    use crate::game::settings::Settings;
+    use crate::game::state::GridCoords;
+
+    fn new_state() -> State {
+        let settings = Settings { seed: Some(7), ..Settings::default() };
+
+        State::new(&settings, piece_set::load("tetromino").unwrap()).unwrap()
+    }
+
+    /// Returns the column of the hole of row `i`
+    fn get_hole(state: &State, i: usize) -> usize {
+        let board = state.get_board();
+
+        match (0..board.get_width()).find(|&j| board.get_cell(i, j) == Cell::Empty) {
+            Some(hole) => hole,
+            None => panic!("Row {i} has no hole")
+        }
+    }
+
+    #[test]
+    fn adding_no_row_never_tops_out() {
+        let mut state = new_state();
+        state.get_board_mutref().lock_cell_at(GridCoords { i: 0, j: 0 }, Cell::Garbage, 0);
+
+        state.add_garbage_rows(0, GarbageHoles::Random);
+
+        assert!(!state.is_game_over());
+        assert!(state.get_board().get_cell(0, 0) == Cell::Garbage);
+    }
+
+    #[test]
+    fn blocks_already_above_the_field_do_not_top_out() {
+        let mut state = new_state();
+        state.get_board_mutref().lock_cell_at(GridCoords { i: 1, j: 0 }, Cell::Garbage, 0);
+
+        state.add_garbage_rows(1, GarbageHoles::Random);
+
+        assert!(!state.is_game_over());
+    }
+
+    #[test]
+    fn blocks_pushed_above_the_field_top_out() {
+        let mut state = new_state();
+        let top_row = state.get_board().get_vanish_zone_height() + 2;
+        state.get_board_mutref().lock_cell_at(GridCoords { i: top_row, j: 0 }, Cell::Garbage, 0);
+
+        state.add_garbage_rows(2, GarbageHoles::Random);
+        assert!(!state.is_game_over());
+
+        state.add_garbage_rows(1, GarbageHoles::Random);
+        assert!(state.is_game_over());
+    }
+
+    #[test]
+    fn column_holes_are_clamped_to_the_board() {
+        let mut state = new_state();
+        let bottom_row = state.get_board().get_height() - 1;
+
+        state.add_garbage_rows(3, GarbageHoles::Column(99));
+
+        for i in bottom_row - 2..=bottom_row {
+            assert_eq!(get_hole(&state, i), state.get_board().get_width() - 1);
+        }
+    }
+
+    #[test]
+    fn messy_holes_always_moving_never_repeat_a_column() {
+        let mut state = new_state();
+        let bottom_row = state.get_board().get_height() - 1;
+
+        state.add_garbage_rows(15, GarbageHoles::Messy(100));
+
+        for i in bottom_row - 14..bottom_row {
+            assert_ne!(get_hole(&state, i), get_hole(&state, i + 1), "row {i}");
+        }
+    }
+
+    #[test]
+    fn messy_holes_never_moving_stay_in_their_column() {
+        let mut state = new_state();
+        let bottom_row = state.get_board().get_height() - 1;
+
+        state.add_garbage_rows(15, GarbageHoles::Messy(0));
+
+        for i in bottom_row - 14..bottom_row {
+            assert_eq!(get_hole(&state, i), get_hole(&state, i + 1), "row {i}");
+        }
+    }
+}
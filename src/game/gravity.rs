@@ -0,0 +1,143 @@
+use crate::game;
+
+/// Gravity unit : one cell per frame of 1/60 s, as used by every ruleset.
+/// Gravities are stored in 1/65536 of this unit to handle slow levels.
+pub const G: u64 = 65536;
+
+/// From 20G on, tetrominos appear directly on the stack
+pub const MAX_GRAVITY: u64 = 20 * G;
+
+const FRAMES_PER_SECOND: u64 = 60;
+
+// Frames per cell of the NES version, from level 0 to level 29 and above
+const NES_FRAMES_PER_CELL: [u64; 30] = [
+    48, 43, 38, 33, 28, 23, 18, 13, 8, 6,
+    5, 5, 5, 4, 4, 4, 3, 3, 3, 2,
+    2, 2, 2, 2, 2, 2, 2, 2, 2, 1
+];
+
+// Internal gravity of TGM in 1/256 G, each applying from the given level on.
+// It drops back at level 200 before climbing to 20G at level 500.
+const TGM_GRAVITY: [(u32, u64); 30] = [
+    (0, 4), (30, 6), (35, 8), (40, 10), (50, 12), (60, 16), (70, 32), (80, 48),
+    (90, 64), (100, 80), (120, 96), (140, 112), (160, 128), (170, 144), (200, 4),
+    (220, 32), (230, 64), (233, 96), (236, 128), (239, 160), (243, 192), (247, 224),
+    (251, 256), (300, 512), (330, 768), (360, 1024), (400, 1280), (420, 1024),
+    (450, 768), (500, 5120)
+];
+
+/// Maps the level to the speed at which tetrominos fall
+#[derive(Clone)]
+pub enum GravityCurve {
+    /// Curve of the Tetris guideline : (0.8 - (level - 1) * 0.007) ^ (level - 1)
+    /// seconds per cell
+    Guideline,
+    /// Frames per cell of the NES version, its level 0 being our level 1
+    Nes,
+    /// Gravity of the TGM master mode, from level 0 up to 20G at level 500
+    Tgm,
+    /// Gravity of each level from level 1, the last one used for higher levels
+    Custom(Vec<u64>)
+}
+
+impl GravityCurve {
+    /// Parses `guideline`, `nes`, `tgm`, or `custom:` followed by the gravity of
+    /// each level in G separated by commas, e.g. `custom:0.02,0.1,1,20`
+    pub fn from_name(name: &str) -> Result<GravityCurve, String> {
+        match name {
+            "guideline" => Ok(GravityCurve::Guideline),
+            "nes" => Ok(GravityCurve::Nes),
+            "tgm" => Ok(GravityCurve::Tgm),
+            _ => match name.strip_prefix("custom:") {
+                Some(gravities) => parse_custom_gravities(gravities),
+                None => Err(format!("Unknown gravity curve '{name}', expected guideline, nes, tgm or custom:<G>,<G>,..."))
+            }
+        }
+    }
+
+    /// Returns the gravity at `level`, in 1/65536 G
+    pub fn get_gravity(&self, level: u32) -> u64 {
+        let level_index = level.max(1) as usize - 1;
+
+        let gravity = match self {
+            GravityCurve::Guideline => get_guideline_gravity(i32::try_from(level_index).unwrap_or(i32::MAX)),
+            GravityCurve::Nes => {
+                let frames_per_cell = NES_FRAMES_PER_CELL[level_index.min(NES_FRAMES_PER_CELL.len() - 1)];
+                G / frames_per_cell
+            },
+            GravityCurve::Tgm => {
+                let (_, gravity) = TGM_GRAVITY
+                    .iter()
+                    .rev()
+                    .find(|(from_level, _)| level >= *from_level)
+                    .unwrap_or(&TGM_GRAVITY[0]);
+
+                gravity * G / 256
+            },
+            GravityCurve::Custom(gravities) => gravities[level_index.min(gravities.len() - 1)]
+        };
+
+        gravity.min(MAX_GRAVITY)
+    }
+}
+
+/// Converts a gravity in 1/65536 G into 1/65536 cells per frame of the game loop
+pub fn per_game_frame(gravity: u64) -> u64 {
+    gravity * game::SLEEP_TIME_BETWEEN_FRAMES_MILLIS * FRAMES_PER_SECOND / 1000
+}
+
+fn get_guideline_gravity(level_index: i32) -> u64 {
+    let base = 0.8 - level_index as f64 * 0.007;
+
+    if base <= 0.0 {
+        return MAX_GRAVITY;
+    }
+
+    let seconds_per_cell = base.powi(level_index);
+    let gravity = G as f64 / (seconds_per_cell * FRAMES_PER_SECOND as f64);
+
+    (gravity as u64).min(MAX_GRAVITY)
+}
+
+fn parse_custom_gravities(gravities: &str) -> Result<GravityCurve, String> {
+    let mut parsed_gravities = Vec::new();
+
+    for gravity in gravities.split(',') {
+        match gravity.trim().parse::<f64>() {
+            Ok(value) if value > 0.0 => parsed_gravities.push((value * G as f64) as u64),
+            _ => return Err(format!("Invalid gravity '{gravity}', expected a positive number of G"))
+        }
+    }
+
+    Ok(GravityCurve::Custom(parsed_gravities))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gravity_stays_within_bounds_at_every_level() {
+        let curves = [
+            GravityCurve::Guideline,
+            GravityCurve::Nes,
+            GravityCurve::Tgm,
+            GravityCurve::from_name("custom:0.02,1,20,1000").unwrap()
+        ];
+        let levels = (0..=1000).chain([u32::MAX]);
+
+        for curve in &curves {
+            for level in levels.clone() {
+                let gravity = curve.get_gravity(level);
+
+                assert!(gravity > 0 && gravity <= MAX_GRAVITY, "level {level}");
+            }
+        }
+    }
+
+    #[test]
+    fn highest_levels_reach_20g() {
+        assert_eq!(GravityCurve::Guideline.get_gravity(u32::MAX), MAX_GRAVITY);
+        assert_eq!(GravityCurve::Tgm.get_gravity(u32::MAX), MAX_GRAVITY);
+    }
+}
@@ -0,0 +1,21 @@
+/// How often the player may swap the current tetromino with the held one
+#[derive(Clone, Copy, PartialEq)]
+pub enum HoldRule {
+    /// No hold at all, the HOLD section is hidden
+    Disabled,
+    /// One swap per tetromino until it locks, as in the guideline
+    OncePerPiece,
+    /// Swaps allowed at any time, to practice
+    Unlimited
+}
+
+impl HoldRule {
+    pub fn from_name(name: &str) -> Result<HoldRule, String> {
+        match name {
+            "none" => Ok(HoldRule::Disabled),
+            "guideline" => Ok(HoldRule::OncePerPiece),
+            "unlimited" => Ok(HoldRule::Unlimited),
+            _ => Err(format!("Unknown hold rule '{name}', expected none, guideline or unlimited"))
+        }
+    }
+}
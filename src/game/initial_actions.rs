@@ -0,0 +1,59 @@
+use crate::game::tetromino::RotationDirection;
+
+/// Initial Hold System (IHS) and Initial Rotation System (IRS) : hold and
+/// rotation keys held when a tetromino spawns act on it right away.
+///
+/// When the terminal reports key releases, keys count as long as they are
+/// held. Otherwise, presses during the line clear and entry delays are
+/// remembered until the next spawn.
+#[derive(Default)]
+pub struct InitialActions {
+    release_events: bool,
+    rotation: Option<RotationDirection>,
+    hold: bool
+}
+
+impl InitialActions {
+    pub fn set_release_events_flag(&mut self, value: bool) {
+        self.release_events = value
+    }
+
+    /// Remembers a rotation key pressed, `falling` telling whether a
+    /// tetromino is on the grid
+    pub fn press_rotation(&mut self, direction: RotationDirection, falling: bool) {
+        if self.release_events || !falling {
+            self.rotation = Some(direction);
+        }
+    }
+
+    pub fn release_rotation(&mut self, direction: RotationDirection) {
+        if self.rotation == Some(direction) {
+            self.rotation = None;
+        }
+    }
+
+    /// Remembers the hold key pressed, `falling` telling whether a
+    /// tetromino is on the grid
+    pub fn press_hold(&mut self, falling: bool) {
+        if self.release_events || !falling {
+            self.hold = true;
+        }
+    }
+
+    pub fn release_hold(&mut self) {
+        self.hold = false
+    }
+
+    /// Returns whether to hold and how to rotate the tetromino spawning.
+    /// Remembered presses are forgotten once used.
+    pub fn take(&mut self) -> (bool, Option<RotationDirection>) {
+        let actions = (self.hold, self.rotation);
+
+        if !self.release_events {
+            self.hold = false;
+            self.rotation = None;
+        }
+
+        actions
+    }
+}
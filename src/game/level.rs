@@ -0,0 +1,159 @@
+use crate::game::state::State;
+
+/// Levels in a section, at the end of which spawns stop levelling up under
+/// the level by piece progression
+pub const SECTION_LEVELS: u32 = 100;
+
+const LINES_PER_LEVEL: u32 = 10;
+
+/// How the level goes up during a game
+#[derive(Clone, Copy, PartialEq)]
+pub enum LevelProgress {
+    /// From level 1, one level every 10 lines
+    Lines,
+    /// From level 0 as in TGM, one level per tetromino spawned and one per
+    /// line cleared. Spawns never pass the last level of a section (xx99)
+    /// nor the level before the last one : only line clears do.
+    Pieces
+}
+
+impl LevelProgress {
+    pub fn get_start_level(&self) -> u32 {
+        match self {
+            LevelProgress::Lines => 1,
+            LevelProgress::Pieces => 0
+        }
+    }
+}
+
+/// Clock and score when a section of levels was completed
+#[derive(Clone, Copy)]
+pub struct SectionRecord {
+    pub clock: u128,
+    pub score: u32
+}
+
+impl State {
+    /// Levels up after a tetromino spawned, under the level by piece
+    /// progression
+    pub fn level_up_on_spawn(&mut self) {
+        let level = self.get_level();
+
+        if self.get_mode().get_level_progress() == LevelProgress::Pieces
+            && level % SECTION_LEVELS != SECTION_LEVELS - 1
+            && level + 1 < self.get_mode().get_max_level() {
+
+            self.change_level(level + 1);
+        }
+    }
+
+    /// Levels up after a lock cleared `cleared_lines`, the lines counter
+    /// being `old_lines` before it
+    pub fn level_up_on_clear(&mut self, old_lines: u32, cleared_lines: u32) {
+        let level = match self.get_mode().get_level_progress() {
+            LevelProgress::Lines
+                if old_lines / LINES_PER_LEVEL < (old_lines + cleared_lines) / LINES_PER_LEVEL =>
+                self.get_level() + 1,
+            LevelProgress::Lines => return,
+            LevelProgress::Pieces => self.get_level() + cleared_lines
+        };
+
+        self.change_level(level.min(self.get_mode().get_max_level()));
+    }
+
+    /// Switches to the delays the mode gives for the current level, if any
+    pub fn update_level_delays(&mut self) {
+        if let Some(delays) = self.get_mode().get_delays(self.get_level()) {
+            self.set_delays(delays);
+        }
+    }
+
+    /// Sets the level, recording the sections completed on the way
+    fn change_level(&mut self, level: u32) {
+        for _ in (self.get_level() / SECTION_LEVELS)..(level / SECTION_LEVELS) {
+            self.add_section_record(SectionRecord {
+                clock: self.get_clock(),
+                score: self.get_score()
+            });
+        }
+
+        self.set_level(level);
+        self.update_level_delays();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::modes::ModeKind;
+    use crate::game::piece_set;
+    use crate::game::settings::Settings;
+
+    fn new_state(mode: ModeKind) -> State {
+        let settings = Settings { mode, ..Settings::default() };
+
+        State::new(&settings, piece_set::load("tetromino").unwrap()).unwrap()
+    }
+
+    #[test]
+    fn pieces_stop_at_the_end_of_a_section() {
+        let mut state = new_state(ModeKind::Master);
+        state.set_level(97);
+
+        state.level_up_on_spawn();
+        assert_eq!(state.get_level(), 98);
+
+        for _ in 0..3 {
+            state.level_up_on_spawn();
+            assert_eq!(state.get_level(), 99);
+        }
+
+        state.level_up_on_clear(0, 1);
+        assert_eq!(state.get_level(), 100);
+    }
+
+    #[test]
+    fn pieces_never_reach_the_last_level() {
+        let mut state = new_state(ModeKind::Master);
+        state.set_level(997);
+
+        state.level_up_on_spawn();
+        state.level_up_on_spawn();
+        assert_eq!(state.get_level(), 998);
+
+        state.level_up_on_clear(0, 4);
+        assert_eq!(state.get_level(), 999);
+    }
+
+    #[test]
+    fn sections_are_recorded_when_completed() {
+        let mut state = new_state(ModeKind::Master);
+        state.set_level(95);
+        state.add_to_score(1234);
+        for _ in 0..5 {
+            state.increment_clock();
+        }
+
+        state.level_up_on_clear(0, 4);
+        assert!(state.get_section_records().is_empty());
+
+        state.level_up_on_clear(4, 1);
+        let records = state.get_section_records();
+        assert_eq!(records.len(), 1);
+        assert_eq!((records[0].clock, records[0].score), (5, 1234));
+    }
+
+    #[test]
+    fn lines_level_up_every_ten_lines() {
+        let mut state = new_state(ModeKind::Endless);
+        assert_eq!(state.get_level(), 1);
+
+        state.level_up_on_spawn();
+        state.level_up_on_clear(0, 9);
+        assert_eq!(state.get_level(), 1);
+
+        state.level_up_on_clear(9, 2);
+        assert_eq!(state.get_level(), 2);
+        assert!(state.get_section_records().is_empty());
+    }
+}
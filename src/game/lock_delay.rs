@@ -0,0 +1,165 @@
+/// Delay between the moment the current tetromino touches down and the
+/// moment it locks. Successful moves and rotations restart the delay, up to
+/// `max_resets` times per tetromino, and a move past that cap locks it at
+/// once ; reaching a new lowest row gives the resets back.
+pub struct LockDelay {
+    duration: u128,
+    max_resets: u32,
+    remaining: Option<u128>,
+    resets: u32,
+    lowest_row: i32
+}
+
+impl LockDelay {
+    pub fn new(duration: u128, max_resets: u32) -> LockDelay {
+        LockDelay {
+            duration,
+            max_resets,
+            remaining: None,
+            resets: 0,
+            lowest_row: i32::MIN
+        }
+    }
+
+    /// Changes the delay given to the next tetrominos touching down
+    pub fn set_duration(&mut self, duration: u128) {
+        self.duration = duration;
+    }
+
+    /// Forgets everything about the previous tetromino
+    pub fn restart(&mut self, row: i32) {
+        self.remaining = None;
+        self.resets = 0;
+        self.lowest_row = row;
+    }
+
+    /// Counts one frame spent touching down.
+    /// Returns true when the tetromino must lock.
+    pub fn tick_grounded(&mut self) -> bool {
+        if self.resets > self.max_resets {
+            return true;
+        }
+
+        let remaining = self.remaining.unwrap_or(self.duration);
+
+        if remaining == 0 {
+            return true;
+        }
+
+        self.remaining = Some(remaining - 1);
+        false
+    }
+
+    /// Stops the delay while the tetromino is in the air
+    pub fn cancel(&mut self) {
+        self.remaining = None;
+    }
+
+    /// Restarts the delay after a successful move or rotation on the ground,
+    /// if any reset is left
+    pub fn on_tetromino_moved(&mut self) {
+        if self.remaining.is_some() {
+            self.resets = self.resets.saturating_add(1);
+
+            if self.resets <= self.max_resets {
+                self.remaining = Some(self.duration);
+            }
+        }
+    }
+
+    /// Gives the resets back when the tetromino falls lower than ever
+    pub fn on_tetromino_fell(&mut self, row: i32) {
+        if row > self.lowest_row {
+            self.lowest_row = row;
+            self.resets = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ticks until the tetromino must lock, returning the frames spent
+    fn frames_until_lock(lock_delay: &mut LockDelay) -> u128 {
+        let mut frames = 1;
+
+        while !lock_delay.tick_grounded() {
+            frames += 1;
+        }
+
+        frames
+    }
+
+    #[test]
+    fn delay_expires_after_its_duration() {
+        let mut lock_delay = LockDelay::new(50, 15);
+        lock_delay.restart(0);
+
+        assert_eq!(frames_until_lock(&mut lock_delay), 51);
+    }
+
+    #[test]
+    fn move_on_the_ground_restarts_the_delay() {
+        let mut lock_delay = LockDelay::new(50, 15);
+        lock_delay.restart(0);
+
+        for _ in 0..30 {
+            assert!(!lock_delay.tick_grounded());
+        }
+        lock_delay.on_tetromino_moved();
+
+        assert_eq!(frames_until_lock(&mut lock_delay), 51);
+    }
+
+    #[test]
+    fn move_in_the_air_is_not_a_reset() {
+        let mut lock_delay = LockDelay::new(50, 0);
+        lock_delay.restart(0);
+
+        lock_delay.on_tetromino_moved();
+
+        assert_eq!(frames_until_lock(&mut lock_delay), 51);
+    }
+
+    #[test]
+    fn move_past_the_cap_locks_at_once() {
+        let mut lock_delay = LockDelay::new(50, 2);
+        lock_delay.restart(0);
+
+        for _ in 0..2 {
+            assert!(!lock_delay.tick_grounded());
+            lock_delay.on_tetromino_moved();
+        }
+        assert!(!lock_delay.tick_grounded());
+
+        lock_delay.on_tetromino_moved();
+        assert!(lock_delay.tick_grounded());
+    }
+
+    #[test]
+    fn zero_resets_keeps_the_full_delay() {
+        let mut lock_delay = LockDelay::new(50, 0);
+        lock_delay.restart(0);
+
+        assert_eq!(frames_until_lock(&mut lock_delay), 51);
+
+        lock_delay.restart(0);
+        assert!(!lock_delay.tick_grounded());
+        lock_delay.on_tetromino_moved();
+        assert!(lock_delay.tick_grounded());
+    }
+
+    #[test]
+    fn new_lowest_row_gives_the_resets_back() {
+        let mut lock_delay = LockDelay::new(50, 0);
+        lock_delay.restart(0);
+
+        assert!(!lock_delay.tick_grounded());
+        lock_delay.on_tetromino_moved();
+        lock_delay.cancel();
+        lock_delay.on_tetromino_fell(1);
+
+        assert_eq!(frames_until_lock(&mut lock_delay), 51);
+    }
+}
@@ -2,49 +2,82 @@
 mod core;
 mod view;
 
-const  GRID_WIDTH: usize = 10;
-const GRID_HEIGHT: usize = 20;
-const GRID_LENGTH: usize = GRID_HEIGHT * GRID_WIDTH;
-
-const NEXT_TETROMINOS_QUEUE_SIZE: usize = 3;
+const MAX_NEXT_TETROMINOS_QUEUE_SIZE: usize = 7;
 
 const SLEEP_TIME_BETWEEN_FRAMES_MILLIS: u64 = 10;
 
+/// Converts a duration in milliseconds into a number of frames
+fn millis_to_frames(millis: u64) -> u128 {
+    (millis / SLEEP_TIME_BETWEEN_FRAMES_MILLIS) as u128
+}
+
+mod board;
 mod tetromino;
 mod tetromino_collision;
 mod cell;
 mod game_action;
+mod top_out;
+mod garbage;
+mod lock_delay;
+mod auto_repeat;
+mod hold;
+mod initial_actions;
+mod gravity;
+mod level;
+mod phase;
+mod piece_set;
+mod rotation_system;
+mod scoring;
+mod randomizer;
+mod modes;
+mod settings;
+mod stack_visibility;
 
 mod state;
 
 use std::io;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use crate::game::game_action::GameAction;
+use crate::game::game_action::{GameAction, InputEvent};
 
-pub fn start_game() -> Result<(), String> {
-    let mut state = state::State::new();
+pub fn start_game(args: impl Iterator<Item = String>) -> Result<(), String> {
+    let settings = settings::Settings::from_args(args)?;
+    let piece_set = piece_set::load(&settings.pieces)?;
+    settings.check_piece_set(&piece_set)?;
 
-    let mut view_struct = match view::initialize_view() {
+    let mut state = state::State::new(&settings, piece_set)?;
+
+    let mut view_struct = match view::initialize_view(&state) {
         Ok (view_struct) => view_struct,
         Err(e) => return Err(e.to_string()),
     };
 
-    loop {
+    state.set_key_release_events_flag(view_struct.has_key_release_events());
+
+    let frame_duration = Duration::from_millis(SLEEP_TIME_BETWEEN_FRAMES_MILLIS);
+    let mut next_frame_time = Instant::now();
 
-        if let Some(next_action) = view::input::read() {
-            match next_action {
-                GameAction::Quit => break,
-                GameAction::Pause => state.flip_paused_flag(),
-                _ => {
-                    if !state.is_game_paused() {
-                        core::perform_action(&mut state, next_action);
+    'game: loop {
+
+        while let Some(input_event) = view::input::read() {
+            match input_event {
+                InputEvent::Pressed(GameAction::Quit) => break 'game,
+                InputEvent::Pressed(GameAction::ToggleGhost) => view_struct.flip_ghost_flag(),
+                InputEvent::Pressed(GameAction::Pause) => {
+                    if !state.is_game_over() {
+                        state.flip_paused_flag();
+                    }
+                },
+                InputEvent::Pressed(action) => {
+                    if !state.is_game_paused() && !state.is_game_over() {
+                        core::press_action(&mut state, action);
                     }
-                }
+                },
+                InputEvent::Released(action) => core::release_action(&mut state, action)
             }
         }
 
-        if !state.is_game_paused() {
+        if !state.is_game_paused() && !state.is_game_over() {
             core::increment_clock_and_trigger_events(&mut state);
         }
 
@@ -53,12 +86,17 @@ pub fn start_game() -> Result<(), String> {
             io::Result::Err(e) => return Err(e.to_string())
         }
 
-        std::thread::sleep(Duration::from_millis(SLEEP_TIME_BETWEEN_FRAMES_MILLIS));
+        // Sleeping until the next frame rather than for a whole frame keeps
+        // the clock in step with real time, which timed modes rely on
+        next_frame_time += frame_duration;
+        std::thread::sleep(next_frame_time.saturating_duration_since(Instant::now()));
     }
     match view::close_view(&mut view_struct) {
         Ok(_) => (),
         io::Result::Err(e) => return Err(e.to_string())
     };
 
+    println!("\n\nSeed : {}", state.get_seed());
+
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,49 @@
+use crate::game::garbage::GarbageHoles;
+use crate::game::modes::{self, GameMode, HudStat};
+use crate::game::state::State;
+
+/// Downstacking practice : clear every garbage row the board starts with,
+/// while more rows may keep coming in on a timer
+pub struct DigMode {
+    /// Frames between two garbage rows pushed in, None for no timer
+    garbage_interval: Option<u128>,
+    holes: GarbageHoles
+}
+
+impl DigMode {
+    pub fn new(garbage_interval: Option<u128>, holes: GarbageHoles) -> DigMode {
+        DigMode { garbage_interval, holes }
+    }
+}
+
+impl GameMode for DigMode {
+    fn get_max_level(&self) -> u32 {
+        1
+    }
+
+    fn is_goal_reached(&self, state: &State) -> bool {
+        state.get_board().count_garbage_lines() == 0
+    }
+
+    fn get_goal_message(&self) -> &'static str {
+        "  CLEARED!"
+    }
+
+    fn get_scheduled_garbage(&self, state: &State) -> Option<(usize, GarbageHoles)> {
+        match self.garbage_interval {
+            Some(interval) if state.get_clock().is_multiple_of(interval) => Some((1, self.holes)),
+            _ => None
+        }
+    }
+
+    fn get_hud_stats(&self, state: &State) -> [HudStat; 3] {
+        [
+            HudStat { label: "TIME", value: modes::format_time(state.get_clock()) },
+            HudStat { label: "PIECES", value: modes::format_counter(state.get_pieces()) },
+            HudStat {
+                label: "GARBAGE",
+                value: modes::format_counter(state.get_board().count_garbage_lines() as u32)
+            }
+        ]
+    }
+}
@@ -0,0 +1,23 @@
+use crate::game::modes::{self, GameMode, HudStat};
+use crate::game::state::State;
+
+/// The original game : levels up every 10 lines until the stack tops out
+pub struct EndlessMode;
+
+impl GameMode for EndlessMode {
+    fn get_max_level(&self) -> u32 {
+        u32::MAX
+    }
+
+    fn is_goal_reached(&self, _state: &State) -> bool {
+        false
+    }
+
+    fn get_hud_stats(&self, state: &State) -> [HudStat; 3] {
+        [
+            HudStat { label: "SCORE", value: modes::format_counter(state.get_score()) },
+            HudStat { label: "LEVEL", value: modes::format_counter(state.get_level()) },
+            HudStat { label: "LINES", value: modes::format_counter(state.get_lines()) }
+        ]
+    }
+}
@@ -0,0 +1,32 @@
+use crate::game::modes::{self, GameMode, HudStat};
+use crate::game::state::State;
+
+/// Clear a fixed number of lines, the level capped along the way
+pub struct MarathonMode {
+    max_level: u32,
+    lines: u32
+}
+
+impl MarathonMode {
+    pub fn new(max_level: u32, lines: u32) -> MarathonMode {
+        MarathonMode { max_level, lines }
+    }
+}
+
+impl GameMode for MarathonMode {
+    fn get_max_level(&self) -> u32 {
+        self.max_level
+    }
+
+    fn is_goal_reached(&self, state: &State) -> bool {
+        state.get_lines() >= self.lines
+    }
+
+    fn get_hud_stats(&self, state: &State) -> [HudStat; 3] {
+        [
+            HudStat { label: "SCORE", value: modes::format_counter(state.get_score()) },
+            HudStat { label: "LEVEL", value: format!("{}/{}", state.get_level(), self.max_level) },
+            HudStat { label: "LINES", value: format!("{}/{}", state.get_lines(), self.lines) }
+        ]
+    }
+}
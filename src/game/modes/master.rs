@@ -0,0 +1,198 @@
+use crate::game;
+use crate::game::level::{self, LevelProgress};
+use crate::game::modes::{self, Delays, GameMode, HudStat};
+use crate::game::scoring::ClearReport;
+use crate::game::state::State;
+
+const MAX_LEVEL: u32 = 999;
+
+// Entry, lock and line clear delays of each section, in milliseconds
+const SECTION_DELAYS_MILLIS: [(u64, u64, u64); 10] = [
+    (417, 500, 667),
+    (417, 500, 667),
+    (417, 500, 667),
+    (417, 500, 667),
+    (417, 500, 667),
+    (417, 500, 417),
+    (333, 450, 267),
+    (267, 400, 200),
+    (200, 350, 100),
+    (200, 283, 100)
+];
+
+// Score needed for each grade, from 9 to S9
+const GRADES: [(u32, &str); 18] = [
+    (0, "9"), (400, "8"), (800, "7"), (1400, "6"), (2000, "5"), (3500, "4"),
+    (5500, "3"), (8000, "2"), (12000, "1"), (16000, "S1"), (22000, "S2"),
+    (30000, "S3"), (40000, "S4"), (52000, "S5"), (66000, "S6"), (82000, "S7"),
+    (100000, "S8"), (120000, "S9")
+];
+
+// Requirements of the GM grade : score and time when completing levels
+// 300 and 500, then when reaching level 999
+const GM_CHECKPOINTS: [(usize, u32, u64); 2] = [
+    (2, 12000, 4 * 60 * 1000 + 15 * 1000),
+    (4, 40000, 7 * 60 * 1000)
+];
+const GM_SCORE: u32 = 126000;
+const GM_TIME_MILLIS: u64 = 13 * 60 * 1000 + 30 * 1000;
+
+/// Reach level 999 under TGM rules : level by piece, gravity up to 20G,
+/// shorter delays from section to section and a grade earned on the way
+pub struct MasterMode;
+
+impl MasterMode {
+    /// Returns the grade of the player, GM requiring level 999 reached in
+    /// time with every checkpoint passed
+    fn get_grade(&self, state: &State) -> &'static str {
+        if self.is_goal_reached(state) && self.are_gm_requirements_met(state) {
+            return "GM";
+        }
+
+        GRADES
+            .iter()
+            .rev()
+            .find(|(score, _)| state.get_score() >= *score)
+            .map_or(GRADES[0].1, |(_, grade)| grade)
+    }
+
+    fn are_gm_requirements_met(&self, state: &State) -> bool {
+        let records = state.get_section_records();
+
+        let checkpoints_passed = GM_CHECKPOINTS.iter().all(|(section, score, time_millis)| {
+            records.get(*section).is_some_and(|record| {
+                record.score >= *score && record.clock <= game::millis_to_frames(*time_millis)
+            })
+        });
+
+        checkpoints_passed
+            && state.get_score() >= GM_SCORE
+            && state.get_clock() <= game::millis_to_frames(GM_TIME_MILLIS)
+    }
+}
+
+impl GameMode for MasterMode {
+    fn get_max_level(&self) -> u32 {
+        MAX_LEVEL
+    }
+
+    fn get_level_progress(&self) -> LevelProgress {
+        LevelProgress::Pieces
+    }
+
+    fn get_delays(&self, level: u32) -> Option<Delays> {
+        let section = (level / level::SECTION_LEVELS) as usize;
+        let (entry, lock, line_clear) = SECTION_DELAYS_MILLIS[section.min(SECTION_DELAYS_MILLIS.len() - 1)];
+
+        Some(Delays {
+            lock: game::millis_to_frames(lock),
+            line_clear: game::millis_to_frames(line_clear),
+            entry: game::millis_to_frames(entry)
+        })
+    }
+
+    /// Scores as a simplified TGM : the level and lines rounded up to a
+    /// quarter, times the lines, the combo and 4 for a perfect clear (bravo).
+    /// TGM also adds the frames the soft drop was held to the first term,
+    /// which is left out, and grows its combo by twice the lines minus 2 on
+    /// each clear, where this one counts the clearing locks.
+    fn get_clear_points(&self, report: &ClearReport, level: u32) -> u32 {
+        let perfect_clear_factor = if report.perfect_clear { 4 } else { 1 };
+
+        (level + report.lines).div_ceil(4)
+            * report.lines
+            * (report.combo + 1)
+            * perfect_clear_factor
+    }
+
+    fn is_goal_reached(&self, state: &State) -> bool {
+        state.get_level() >= MAX_LEVEL
+    }
+
+    fn get_hud_stats(&self, state: &State) -> [HudStat; 3] {
+        let section_end = (state.get_level() / level::SECTION_LEVELS + 1) * level::SECTION_LEVELS;
+
+        [
+            HudStat { label: "GRADE", value: String::from(self.get_grade(state)) },
+            HudStat {
+                label: "LEVEL",
+                value: format!("{}/{}", state.get_level(), section_end.min(MAX_LEVEL))
+            },
+            HudStat { label: "TIME", value: modes::format_time(state.get_clock()) }
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::level::SectionRecord;
+    use crate::game::modes::ModeKind;
+    use crate::game::piece_set;
+    use crate::game::settings::Settings;
+
+    /// Returns a master game at level 999 with the GM score, whose sections
+    /// ended at `records` (score, time in milliseconds)
+    fn finished_game(records: [(u32, u64); 9]) -> State {
+        let settings = Settings { mode: ModeKind::Master, ..Settings::default() };
+        let mut state = State::new(&settings, piece_set::load("tetromino").unwrap()).unwrap();
+
+        for (score, time_millis) in records {
+            state.add_section_record(SectionRecord { clock: game::millis_to_frames(time_millis), score });
+        }
+        state.set_level(MAX_LEVEL);
+        state.add_to_score(GM_SCORE);
+
+        state
+    }
+
+    fn records_passing_checkpoints() -> [(u32, u64); 9] {
+        let mut records = [(0, 0); 9];
+        records[2] = (12000, 4 * 60 * 1000 + 15 * 1000);
+        records[4] = (40000, 7 * 60 * 1000);
+
+        records
+    }
+
+    #[test]
+    fn gm_needs_level_999_score_and_checkpoints() {
+        let mut state = finished_game(records_passing_checkpoints());
+        assert_eq!(MasterMode.get_grade(&state), "GM");
+
+        state.set_level(MAX_LEVEL - 1);
+        assert_eq!(MasterMode.get_grade(&state), "S9");
+    }
+
+    #[test]
+    fn gm_needs_the_score_of_each_checkpoint() {
+        for section in [2, 4] {
+            let mut records = records_passing_checkpoints();
+            records[section].0 -= 1;
+
+            assert_eq!(MasterMode.get_grade(&finished_game(records)), "S9", "section {section}");
+        }
+    }
+
+    #[test]
+    fn gm_needs_the_time_of_each_checkpoint() {
+        for section in [2, 4] {
+            let mut records = records_passing_checkpoints();
+            records[section].1 += 10;
+
+            assert_eq!(MasterMode.get_grade(&finished_game(records)), "S9", "section {section}");
+        }
+    }
+
+    #[test]
+    fn grades_follow_the_score() {
+        let settings = Settings { mode: ModeKind::Master, ..Settings::default() };
+        let mut state = State::new(&settings, piece_set::load("tetromino").unwrap()).unwrap();
+        assert_eq!(MasterMode.get_grade(&state), "9");
+
+        state.add_to_score(12000);
+        assert_eq!(MasterMode.get_grade(&state), "1");
+
+        state.add_to_score(200000);
+        assert_eq!(MasterMode.get_grade(&state), "S9");
+    }
+}
@@ -0,0 +1,171 @@
+mod dig;
+mod endless;
+mod marathon;
+mod master;
+mod sprint;
+mod ultra;
+mod zen;
+
+use crate::game;
+use crate::game::garbage::GarbageHoles;
+use crate::game::gravity::GravityCurve;
+use crate::game::level::LevelProgress;
+use crate::game::scoring::ClearReport;
+use crate::game::settings::Settings;
+use crate::game::state::State;
+
+use dig::DigMode;
+use endless::EndlessMode;
+use marathon::MarathonMode;
+use master::MasterMode;
+use sprint::SprintMode;
+use ultra::UltraMode;
+use zen::ZenMode;
+
+/// Counter shown in one of the three boxes on the left of the board
+pub struct HudStat {
+    pub label: &'static str,
+    pub value: String
+}
+
+/// Delays in frames, replacing the ones of the settings
+#[derive(Clone, Copy)]
+pub struct Delays {
+    pub lock: u128,
+    pub line_clear: u128,
+    pub entry: u128
+}
+
+/// Rules and goal of a game, and what the player sees of their progress
+pub trait GameMode {
+    /// Level after which clearing lines no longer levels up
+    fn get_max_level(&self) -> u32;
+
+    fn get_level_progress(&self) -> LevelProgress {
+        LevelProgress::Lines
+    }
+
+    /// Returns the delays at `level`, or None to keep the ones of the
+    /// settings
+    fn get_delays(&self, _level: u32) -> Option<Delays> {
+        None
+    }
+
+    /// Returns the points earned by a lock at `level`
+    fn get_clear_points(&self, report: &ClearReport, level: u32) -> u32 {
+        report.get_points(level)
+    }
+
+    /// Checks if the game ends when the stack tops out. Otherwise the
+    /// stack is cleared and the game goes on.
+    fn can_top_out(&self) -> bool {
+        true
+    }
+
+    /// Checks if the player reached the goal, which ends the game
+    fn is_goal_reached(&self, state: &State) -> bool;
+
+    /// Message shown once the goal is reached
+    fn get_goal_message(&self) -> &'static str {
+        "  FINISHED"
+    }
+
+    /// Returns the garbage rows to push in during this frame, if any
+    fn get_scheduled_garbage(&self, _state: &State) -> Option<(usize, GarbageHoles)> {
+        None
+    }
+
+    fn get_hud_stats(&self, state: &State) -> [HudStat; 3];
+}
+
+/// Modes that can be selected at startup
+#[derive(Clone, Copy)]
+pub enum ModeKind {
+    /// No goal, levels up every 10 lines until the game tops out
+    Endless,
+    /// 150 lines from level 1 to 15
+    Marathon,
+    /// 40 lines as fast as possible
+    Sprint,
+    /// Highest score in 2 minutes
+    Ultra,
+    /// No top-out and no gravity increase
+    Zen,
+    /// Clear the garbage rows the board starts with
+    Dig,
+    /// TGM rules from level 0 to 999, up to 20G, graded from 9 to GM
+    Master
+}
+
+const MARATHON_MAX_LEVEL: u32 = 15;
+const MARATHON_LINES: u32 = 150;
+const SPRINT_LINES: u32 = 40;
+const ULTRA_DURATION_MILLIS: u64 = 2 * 60 * 1000;
+const DIG_GARBAGE_ROWS: usize = 10;
+
+impl ModeKind {
+    pub fn from_name(name: &str) -> Result<ModeKind, String> {
+        match name {
+            "endless" => Ok(ModeKind::Endless),
+            "marathon" => Ok(ModeKind::Marathon),
+            "sprint" => Ok(ModeKind::Sprint),
+            "ultra" => Ok(ModeKind::Ultra),
+            "zen" => Ok(ModeKind::Zen),
+            "dig" => Ok(ModeKind::Dig),
+            "master" => Ok(ModeKind::Master),
+            _ => Err(format!("Unknown mode '{name}', expected endless, marathon, sprint, ultra, zen, dig or master"))
+        }
+    }
+
+    /// Returns the garbage rows the board starts with when not given
+    pub fn get_default_garbage_rows(&self) -> usize {
+        match self {
+            ModeKind::Dig => DIG_GARBAGE_ROWS,
+            _ => 0
+        }
+    }
+
+    /// Returns the gravity curve the mode imposes, if any
+    pub fn get_gravity_curve(&self) -> Option<GravityCurve> {
+        match self {
+            ModeKind::Master => Some(GravityCurve::Tgm),
+            _ => None
+        }
+    }
+
+    pub fn create(&self, settings: &Settings) -> Box<dyn GameMode> {
+        match self {
+            ModeKind::Endless => Box::new(EndlessMode),
+            ModeKind::Marathon => Box::new(MarathonMode::new(MARATHON_MAX_LEVEL, MARATHON_LINES)),
+            ModeKind::Sprint => Box::new(SprintMode::new(SPRINT_LINES)),
+            ModeKind::Ultra => Box::new(UltraMode::new(game::millis_to_frames(ULTRA_DURATION_MILLIS))),
+            ModeKind::Zen => Box::new(ZenMode),
+            ModeKind::Dig => {
+                let garbage_interval = Some(game::millis_to_frames(settings.garbage_interval_millis))
+                    .filter(|interval| *interval > 0);
+
+                Box::new(DigMode::new(garbage_interval, settings.garbage_holes))
+            },
+            ModeKind::Master => Box::new(MasterMode)
+        }
+    }
+}
+
+/// Formats a number of frames as minutes, seconds and hundredths, dropping
+/// the hundredths from 10 minutes on
+pub fn format_time(frames: u128) -> String {
+    let hundredths = frames * game::SLEEP_TIME_BETWEEN_FRAMES_MILLIS as u128 / 10;
+    let seconds = hundredths / 100;
+    let minutes = seconds / 60;
+
+    if minutes < 10 {
+        format!("{}:{:02}.{:02}", minutes, seconds % 60, hundredths % 100)
+    } else {
+        format!("{}:{:02}", minutes, seconds % 60)
+    }
+}
+
+/// Formats a counter the way the original SCORE, LEVEL and LINES boxes did
+pub fn format_counter(counter: u32) -> String {
+    format!("{counter:07}")
+}
@@ -0,0 +1,35 @@
+use crate::game::modes::{self, GameMode, HudStat};
+use crate::game::state::State;
+
+/// Clear a fixed number of lines as fast as possible, at level 1
+pub struct SprintMode {
+    lines: u32
+}
+
+impl SprintMode {
+    pub fn new(lines: u32) -> SprintMode {
+        SprintMode { lines }
+    }
+}
+
+impl GameMode for SprintMode {
+    fn get_max_level(&self) -> u32 {
+        1
+    }
+
+    fn is_goal_reached(&self, state: &State) -> bool {
+        state.get_lines() >= self.lines
+    }
+
+    fn get_goal_message(&self) -> &'static str {
+        "  CLEARED!"
+    }
+
+    fn get_hud_stats(&self, state: &State) -> [HudStat; 3] {
+        [
+            HudStat { label: "TIME", value: modes::format_time(state.get_clock()) },
+            HudStat { label: "LINES", value: format!("{}/{}", state.get_lines(), self.lines) },
+            HudStat { label: "PIECES", value: modes::format_counter(state.get_pieces()) }
+        ]
+    }
+}
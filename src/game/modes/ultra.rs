@@ -0,0 +1,38 @@
+use crate::game::modes::{self, GameMode, HudStat};
+use crate::game::state::State;
+
+/// Score as much as possible before the time runs out, at level 1
+pub struct UltraMode {
+    duration: u128
+}
+
+impl UltraMode {
+    /// `duration` is in frames
+    pub fn new(duration: u128) -> UltraMode {
+        UltraMode { duration }
+    }
+}
+
+impl GameMode for UltraMode {
+    fn get_max_level(&self) -> u32 {
+        1
+    }
+
+    fn is_goal_reached(&self, state: &State) -> bool {
+        state.get_clock() >= self.duration
+    }
+
+    fn get_goal_message(&self) -> &'static str {
+        "  TIME UP"
+    }
+
+    fn get_hud_stats(&self, state: &State) -> [HudStat; 3] {
+        let remaining = self.duration.saturating_sub(state.get_clock());
+
+        [
+            HudStat { label: "TIME", value: modes::format_time(remaining) },
+            HudStat { label: "SCORE", value: modes::format_counter(state.get_score()) },
+            HudStat { label: "LINES", value: modes::format_counter(state.get_lines()) }
+        ]
+    }
+}
@@ -0,0 +1,28 @@
+use crate::game::modes::{self, GameMode, HudStat};
+use crate::game::state::State;
+
+/// Relaxed play : the gravity stays at level 1 and topping out only
+/// clears the stack
+pub struct ZenMode;
+
+impl GameMode for ZenMode {
+    fn get_max_level(&self) -> u32 {
+        1
+    }
+
+    fn can_top_out(&self) -> bool {
+        false
+    }
+
+    fn is_goal_reached(&self, _state: &State) -> bool {
+        false
+    }
+
+    fn get_hud_stats(&self, state: &State) -> [HudStat; 3] {
+        [
+            HudStat { label: "SCORE", value: modes::format_counter(state.get_score()) },
+            HudStat { label: "LINES", value: modes::format_counter(state.get_lines()) },
+            HudStat { label: "TIME", value: modes::format_time(state.get_clock()) }
+        ]
+    }
+}
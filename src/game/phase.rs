@@ -0,0 +1,11 @@
+/// Steps the engine goes through between two tetrominos
+#[derive(Clone, Copy, PartialEq)]
+pub enum Phase {
+    /// The current tetromino falls under the player control
+    Falling,
+    /// Full lines stay on the grid for the given frames before being removed
+    LineClear(u128),
+    /// The grid stays still for the given frames before the next tetromino
+    /// spawns
+    Entry(u128)
+}
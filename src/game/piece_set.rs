@@ -0,0 +1,315 @@
+use std::fs;
+
+use crossterm::style::Color;
+
+use crate::game::rotation_system::kicks::KickTable;
+use crate::game::scoring;
+use crate::game::tetromino::Tetromino;
+
+/// Largest grid a rotation state may be drawn in, which keeps every piece
+/// inside the HOLD and NEXT boxes
+pub const MAX_GRID_SIZE: usize = 5;
+
+// Rows and columns of the HOLD and NEXT boxes for small pieces
+const MIN_PREVIEW_SIZE: (usize, usize) = (2, 4);
+
+// Color of the pieces without a `color` line
+const DEFAULT_COLOR: Color = Color::Grey;
+// Orange of the 256 color palette, missing from the 16 named colors
+const ORANGE: Color = Color::AnsiValue(208);
+
+const BUILT_IN_SETS: [(&str, &str); 3] = [
+    ("tetromino", include_str!("../../pieces/tetromino.txt")),
+    ("pentomino", include_str!("../../pieces/pentomino.txt")),
+    ("triomino", include_str!("../../pieces/triomino.txt"))
+];
+
+/// Shapes and rules of one piece, as read from a piece set file
+#[derive(Clone)]
+pub struct PieceDefinition {
+    pub name: String,
+    /// Offsets (i, j) of the cells from the pivot, one list per rotation
+    /// state, from the spawn orientation clockwise
+    pub shapes: Vec<Vec<(i32, i32)>>,
+    pub kicks: KickTable,
+    /// Does not kick when the rotation is first blocked in the column of
+    /// the pivot, as the L, J and T of TGM
+    pub center_column: bool,
+    pub color: Color,
+    /// Detects T-spins with the 3-corner rule, only allowed for T shapes
+    pub t_spin: bool
+}
+
+/// Pieces the randomizers deal from, loaded at startup
+pub struct PieceSet {
+    pieces: Vec<PieceDefinition>
+}
+
+impl PieceSet {
+    /// Parses a piece set file, whose format is described in the README
+    pub fn parse(text: &str) -> Result<PieceSet, String> {
+        let mut pieces: Vec<PieceDefinition> = Vec::new();
+        let mut grid: Vec<&str> = Vec::new();
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let line_number = line_index + 1;
+
+            if line.starts_with("//") {
+                continue;
+            }
+
+            if line.is_empty() || line.starts_with("piece ") {
+                add_shape(pieces.last_mut(), &mut grid)
+                    .map_err(|error| format!("line {line_number} : {error}"))?;
+            }
+
+            if let Some(name) = line.strip_prefix("piece ") {
+                pieces.push(PieceDefinition {
+                    name: String::from(name.trim()),
+                    shapes: Vec::new(),
+                    kicks: KickTable::Standard,
+                    center_column: false,
+                    color: DEFAULT_COLOR,
+                    t_spin: false
+                });
+                continue;
+            }
+
+            let piece = match pieces.last_mut() {
+                Some(piece) => piece,
+                None if line.is_empty() => continue,
+                None => return Err(format!("line {line_number} : expected 'piece <name>'"))
+            };
+
+            if let Some(kicks) = line.strip_prefix("kicks ") {
+                piece.kicks = KickTable::from_name(kicks.trim())
+                    .map_err(|error| format!("line {line_number} : {error}"))?;
+            } else if let Some(color) = line.strip_prefix("color ") {
+                piece.color = parse_color(color.trim())
+                    .map_err(|error| format!("line {line_number} : {error}"))?;
+            } else if line == "t-spin" {
+                piece.t_spin = true;
+            } else if line == "center-column" {
+                piece.center_column = true;
+            } else if !line.is_empty() {
+                grid.push(line);
+            }
+        }
+
+        add_shape(pieces.last_mut(), &mut grid)
+            .map_err(|error| format!("end of file : {error}"))?;
+
+        if pieces.is_empty() {
+            return Err(String::from("no piece defined"));
+        }
+
+        for piece in &pieces {
+            check_piece(piece)?;
+        }
+
+        Ok(PieceSet { pieces })
+    }
+
+    pub fn get_pieces(&self) -> &[PieceDefinition] {
+        &self.pieces
+    }
+
+    pub fn get_definition(&self, tetromino: Tetromino) -> &PieceDefinition {
+        &self.pieces[tetromino.get_id()]
+    }
+
+    pub fn get_count(&self) -> u32 {
+        self.pieces.len() as u32
+    }
+
+    /// Returns the piece of the set with this name, if any
+    pub fn find(&self, name: &str) -> Option<Tetromino> {
+        self.pieces
+            .iter()
+            .position(|piece| piece.name == name)
+            .map(|id| Tetromino::from_index(id as u32))
+    }
+
+    /// Returns the rows and columns of the HOLD and NEXT boxes, enough to
+    /// show every piece in its spawn orientation
+    pub fn get_preview_size(&self) -> (usize, usize) {
+        self.pieces
+            .iter()
+            .map(|piece| get_bounding_size(&piece.shapes[0]))
+            .fold(MIN_PREVIEW_SIZE, |(rows, columns), (height, width)| {
+                (rows.max(height), columns.max(width))
+            })
+    }
+
+    /// Returns the columns spanned by the widest rotation state
+    pub fn get_max_width(&self) -> usize {
+        self.pieces
+            .iter()
+            .flat_map(|piece| piece.shapes.iter())
+            .map(|shape| get_bounding_size(shape).1)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Loads the built-in set `name`, or the set file at path `name`
+pub fn load(name: &str) -> Result<PieceSet, String> {
+    let text = match BUILT_IN_SETS.iter().find(|(set_name, _)| *set_name == name) {
+        Some((_, text)) => String::from(*text),
+        None => fs::read_to_string(name)
+            .map_err(|error| format!("Cannot read piece set '{name}' : {error}"))?
+    };
+
+    PieceSet::parse(&text)
+        .map_err(|error| format!("Invalid piece set '{name}', {error}"))
+}
+
+/// Returns the (height, width) of the smallest box holding the shape
+pub fn get_bounding_size(shape: &[(i32, i32)]) -> (usize, usize) {
+    let span = |values: Vec<i32>| {
+        let min = values.iter().min().unwrap_or(&0);
+        let max = values.iter().max().unwrap_or(&-1);
+
+        (max - min + 1) as usize
+    };
+
+    (
+        span(shape.iter().map(|cell| cell.0).collect()),
+        span(shape.iter().map(|cell| cell.1).collect())
+    )
+}
+
+/// Parses the name of a terminal color, such as `cyan` or `dark_red`, or
+/// `orange`
+fn parse_color(name: &str) -> Result<Color, String> {
+    match name {
+        "orange" => Ok(ORANGE),
+        _ => Color::try_from(name).map_err(|_| format!("unknown color '{name}'"))
+    }
+}
+
+/// Turns the grid read so far into a rotation state of `piece`
+fn add_shape(piece: Option<&mut PieceDefinition>, grid: &mut Vec<&str>) -> Result<(), String> {
+    let piece = match piece {
+        Some(piece) if !grid.is_empty() => piece,
+        _ => return Ok(())
+    };
+
+    let size = grid.len();
+
+    if size > MAX_GRID_SIZE || grid.iter().any(|row| row.chars().count() != size) {
+        return Err(format!(
+            "the grids of piece '{}' must be square, with at most {MAX_GRID_SIZE} rows",
+            piece.name
+        ));
+    }
+
+    let pivot = (size as i32 - 1) / 2;
+    let mut shape = Vec::new();
+
+    for (i, row) in grid.iter().enumerate() {
+        for (j, character) in row.chars().enumerate() {
+            match character {
+                '#' => shape.push((i as i32 - pivot, j as i32 - pivot)),
+                '.' => (),
+                _ => return Err(format!("unexpected '{character}' in a grid, expected '#' or '.'"))
+            }
+        }
+    }
+
+    piece.shapes.push(shape);
+    grid.clear();
+
+    Ok(())
+}
+
+fn check_piece(piece: &PieceDefinition) -> Result<(), String> {
+    if ![1, 2, 4].contains(&piece.shapes.len()) {
+        return Err(format!("piece '{}' must have 1, 2 or 4 rotation states", piece.name));
+    }
+
+    let cells = piece.shapes[0].len();
+
+    if cells == 0 || piece.shapes.iter().any(|shape| shape.len() != cells) {
+        return Err(format!(
+            "every rotation state of piece '{}' must have the same cells, at least one",
+            piece.name
+        ));
+    }
+
+    if piece.t_spin && piece.shapes.iter().any(|shape| scoring::get_t_front_corners(shape).is_none()) {
+        return Err(format!(
+            "piece '{}' must be a T around its pivot in every rotation state to detect T-spins",
+            piece.name
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(text: &str) -> String {
+        match PieceSet::parse(text) {
+            Ok(_) => panic!("Parsed an invalid piece set :\n{text}"),
+            Err(error) => error
+        }
+    }
+
+    #[test]
+    fn built_in_sets_parse() {
+        for (name, text) in BUILT_IN_SETS {
+            assert!(PieceSet::parse(text).is_ok(), "set {name}");
+        }
+    }
+
+    #[test]
+    fn set_needs_a_piece() {
+        assert_eq!(parse_error(""), "no piece defined");
+        assert_eq!(parse_error("// comment only\n"), "no piece defined");
+    }
+
+    #[test]
+    fn grid_needs_a_piece_first() {
+        assert_eq!(parse_error("##\n##\n"), "line 1 : expected 'piece <name>'");
+    }
+
+    #[test]
+    fn unknown_directives_are_reported_with_their_line() {
+        assert!(parse_error("piece A\nkicks tgm\n#\n").starts_with("line 2 : unknown kicks 'tgm'"));
+        assert!(parse_error("piece A\ncolor pink\n#\n").starts_with("line 2 : unknown color 'pink'"));
+        assert!(parse_error("piece A\n#x\n##\n").contains("unexpected 'x'"));
+    }
+
+    #[test]
+    fn grids_must_be_small_squares() {
+        assert!(parse_error("piece A\n##.\n##.\n").contains("must be square"));
+        assert!(parse_error(&format!("piece A\n{}", "######\n".repeat(6))).contains("must be square"));
+    }
+
+    #[test]
+    fn rotation_states_must_match() {
+        let three_states = "piece A\n#.\n..\n\n.#\n..\n\n..\n.#\n";
+        assert!(parse_error(three_states).contains("1, 2 or 4 rotation states"));
+
+        let different_cells = "piece A\n##\n..\n\n#.\n..\n";
+        assert!(parse_error(different_cells).contains("same cells"));
+
+        assert!(parse_error("piece A\n..\n..\n").contains("at least one"));
+    }
+
+    #[test]
+    fn t_spins_need_a_t_around_the_pivot() {
+        let t = "piece A\nt-spin\n.#.\n###\n...\n";
+        assert!(PieceSet::parse(t).is_ok());
+
+        let l = "piece A\nt-spin\n..#\n###\n...\n";
+        assert!(parse_error(l).contains("must be a T around its pivot"));
+
+        let off_center_t = "piece A\nt-spin\n###\n.#.\n...\n";
+        assert!(parse_error(off_center_t).contains("must be a T around its pivot"));
+    }
+}
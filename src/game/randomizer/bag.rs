@@ -0,0 +1,81 @@
+use rand::RngCore;
+use rand::seq::SliceRandom;
+
+use crate::game::randomizer::Randomizer;
+use crate::game::tetromino::Tetromino;
+
+/// Deals every one of the `count` pieces `copies` times in a random order,
+/// then refills
+pub struct BagRandomizer {
+    copies: usize,
+    count: u32,
+    bag: Vec<Tetromino>
+}
+
+impl BagRandomizer {
+    pub fn new(copies: usize, count: u32) -> BagRandomizer {
+        BagRandomizer {
+            copies,
+            count,
+            bag: Vec::new()
+        }
+    }
+
+    fn refill(&mut self, rng: &mut dyn RngCore) {
+        for _ in 0..self.copies {
+            for index in 0..self.count {
+                self.bag.push(Tetromino::from_index(index));
+            }
+        }
+
+        self.bag.shuffle(rng);
+    }
+}
+
+impl Randomizer for BagRandomizer {
+    fn next_tetromino(&mut self, rng: &mut dyn RngCore) -> Tetromino {
+        if self.bag.is_empty() {
+            self.refill(rng);
+        }
+
+        match self.bag.pop() {
+            Some(tetromino) => tetromino,
+            None => panic!("Tried to draw from an empty bag right after refilling it")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    /// Checks that each bag of the sequence deals every piece `copies` times
+    fn assert_bags_are_complete(copies: usize) {
+        let count = 7;
+        let mut randomizer = BagRandomizer::new(copies, count as u32);
+        let mut rng = ChaCha8Rng::seed_from_u64(42);
+
+        for _ in 0..20 {
+            let mut dealt = vec![0; count];
+
+            for _ in 0..count * copies {
+                dealt[randomizer.next_tetromino(&mut rng).get_id()] += 1;
+            }
+
+            assert_eq!(dealt, vec![copies; count]);
+        }
+    }
+
+    #[test]
+    fn seven_bag_deals_each_piece_once_per_bag() {
+        assert_bags_are_complete(1);
+    }
+
+    #[test]
+    fn fourteen_bag_deals_each_piece_twice_per_bag() {
+        assert_bags_are_complete(2);
+    }
+}
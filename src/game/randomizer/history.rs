@@ -0,0 +1,73 @@
+use rand::{Rng, RngCore};
+
+use crate::game::randomizer::Randomizer;
+use crate::game::piece_set::PieceSet;
+use crate::game::tetromino::Tetromino;
+
+const HISTORY_SIZE: usize = 4;
+const INITIAL_HISTORY: [&str; HISTORY_SIZE] = ["Z", "S", "Z", "S"];
+const FIRST_PIECE_CHOICES: [&str; 4] = ["I", "T", "J", "L"];
+
+/// TGM style randomizer : a drawn piece already in the history of the last
+/// four pieces is rerolled, up to `rerolls` times. The first piece is never
+/// an S, Z or O. Pieces missing from the piece set are left out of the
+/// initial history and of the first piece choices.
+pub struct HistoryRandomizer {
+    rerolls: u32,
+    count: u32,
+    history: [Option<Tetromino>; HISTORY_SIZE],
+    first_piece_choices: Vec<Tetromino>,
+    is_first_draw: bool
+}
+
+impl HistoryRandomizer {
+    pub fn new(rerolls: u32, piece_set: &PieceSet) -> HistoryRandomizer {
+        HistoryRandomizer {
+            rerolls,
+            count: piece_set.get_count(),
+            history: INITIAL_HISTORY.map(|name| piece_set.find(name)),
+            first_piece_choices: FIRST_PIECE_CHOICES
+                .iter()
+                .filter_map(|name| piece_set.find(name))
+                .collect(),
+            is_first_draw: true
+        }
+    }
+
+    fn push_to_history(&mut self, tetromino: Tetromino) {
+        self.history.rotate_left(1);
+        self.history[HISTORY_SIZE - 1] = Some(tetromino);
+    }
+
+    fn draw_first_piece(&self, rng: &mut dyn RngCore) -> Tetromino {
+        let choices = &self.first_piece_choices;
+
+        if choices.is_empty() {
+            Tetromino::from_index(rng.random_range(0..self.count))
+        } else {
+            choices[rng.random_range(0..choices.len())]
+        }
+    }
+}
+
+impl Randomizer for HistoryRandomizer {
+    fn next_tetromino(&mut self, rng: &mut dyn RngCore) -> Tetromino {
+        let tetromino = if self.is_first_draw {
+            self.is_first_draw = false;
+            self.draw_first_piece(rng)
+        } else {
+            let mut candidate = Tetromino::from_index(rng.random_range(0..self.count));
+            let mut tries = 1;
+
+            while self.history.contains(&Some(candidate)) && tries < self.rerolls {
+                candidate = Tetromino::from_index(rng.random_range(0..self.count));
+                tries += 1;
+            }
+
+            candidate
+        };
+
+        self.push_to_history(tetromino);
+        tetromino
+    }
+}
@@ -0,0 +1,65 @@
+mod bag;
+mod history;
+mod reroll;
+mod pure_random;
+
+use rand::RngCore;
+
+use crate::game::piece_set::PieceSet;
+use crate::game::tetromino::Tetromino;
+
+use bag::BagRandomizer;
+use history::HistoryRandomizer;
+use reroll::RerollRandomizer;
+use pure_random::PureRandomizer;
+
+/// Generates the sequence of tetrominos fed into the next queue
+pub trait Randomizer {
+    /// Draws the next tetromino of the sequence
+    fn next_tetromino(&mut self, rng: &mut dyn RngCore) -> Tetromino;
+}
+
+/// Randomizers that can be selected at startup
+#[derive(Clone, Copy)]
+pub enum RandomizerKind {
+    /// Shuffled bags of the seven tetrominos, as in the guideline
+    SevenBag,
+    /// Shuffled bags of two copies of the seven tetrominos
+    FourteenBag,
+    /// TGM style : rerolls pieces found in the last four ones
+    History,
+    /// NES style : rerolls once if the piece repeats the previous one
+    Nes,
+    /// Every piece drawn independently
+    PureRandom
+}
+
+const SEVEN_BAG_COPIES: usize = 1;
+const FOURTEEN_BAG_COPIES: usize = 2;
+const HISTORY_REROLLS: u32 = 6;
+
+impl RandomizerKind {
+    pub fn from_name(name: &str) -> Result<RandomizerKind, String> {
+        match name {
+            "7-bag" => Ok(RandomizerKind::SevenBag),
+            "14-bag" => Ok(RandomizerKind::FourteenBag),
+            "history" => Ok(RandomizerKind::History),
+            "nes" => Ok(RandomizerKind::Nes),
+            "random" => Ok(RandomizerKind::PureRandom),
+            _ => Err(format!("Unknown randomizer '{name}', expected 7-bag, 14-bag, history, nes or random"))
+        }
+    }
+
+    /// Returns the randomizer dealing the pieces of `piece_set`
+    pub fn create(&self, piece_set: &PieceSet) -> Box<dyn Randomizer> {
+        let count = piece_set.get_count();
+
+        match self {
+            RandomizerKind::SevenBag => Box::new(BagRandomizer::new(SEVEN_BAG_COPIES, count)),
+            RandomizerKind::FourteenBag => Box::new(BagRandomizer::new(FOURTEEN_BAG_COPIES, count)),
+            RandomizerKind::History => Box::new(HistoryRandomizer::new(HISTORY_REROLLS, piece_set)),
+            RandomizerKind::Nes => Box::new(RerollRandomizer::new(count)),
+            RandomizerKind::PureRandom => Box::new(PureRandomizer::new(count))
+        }
+    }
+}
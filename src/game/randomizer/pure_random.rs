@@ -0,0 +1,21 @@
+use rand::{Rng, RngCore};
+
+use crate::game::randomizer::Randomizer;
+use crate::game::tetromino::Tetromino;
+
+/// Draws every one of the `count` pieces independently of the previous ones
+pub struct PureRandomizer {
+    count: u32
+}
+
+impl PureRandomizer {
+    pub fn new(count: u32) -> PureRandomizer {
+        PureRandomizer { count }
+    }
+}
+
+impl Randomizer for PureRandomizer {
+    fn next_tetromino(&mut self, rng: &mut dyn RngCore) -> Tetromino {
+        Tetromino::from_index(rng.random_range(0..self.count))
+    }
+}
@@ -0,0 +1,39 @@
+use rand::{Rng, RngCore};
+
+use crate::game::randomizer::Randomizer;
+use crate::game::tetromino::Tetromino;
+
+/// NES style randomizer : rolls one of the `count` pieces or an extra
+/// outcome, and rerolls once among the pieces if it got the extra outcome
+/// or the previous piece again
+pub struct RerollRandomizer {
+    count: u32,
+    previous: Option<Tetromino>
+}
+
+impl RerollRandomizer {
+    pub fn new(count: u32) -> RerollRandomizer {
+        RerollRandomizer {
+            count,
+            previous: None
+        }
+    }
+}
+
+impl Randomizer for RerollRandomizer {
+    fn next_tetromino(&mut self, rng: &mut dyn RngCore) -> Tetromino {
+        let count = self.count;
+        let roll = rng.random_range(0..(count + 1));
+
+        let tetromino = if roll == count
+            || self.previous == Some(Tetromino::from_index(roll)) {
+
+            Tetromino::from_index(rng.random_range(0..count))
+        } else {
+            Tetromino::from_index(roll)
+        };
+
+        self.previous = Some(tetromino);
+        tetromino
+    }
+}
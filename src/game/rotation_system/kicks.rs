@@ -0,0 +1,152 @@
+// Super Rotation System kick tables, as published in the Tetris guideline :
+// offsets are (x, y) with y pointing upwards, tested in order until the
+// rotated tetromino fits. One line per rotation transition, in the order
+// 0->R, R->0, R->2, 2->R, 2->L, L->2, L->0, 0->L.
+
+const KICKS_JLSTZ: [[(i32, i32); 5]; 8] = [
+    [( 0,  0), (-1,  0), (-1,  1), ( 0, -2), (-1, -2)],
+    [( 0,  0), ( 1,  0), ( 1, -1), ( 0,  2), ( 1,  2)],
+    [( 0,  0), ( 1,  0), ( 1, -1), ( 0,  2), ( 1,  2)],
+    [( 0,  0), (-1,  0), (-1,  1), ( 0, -2), (-1, -2)],
+    [( 0,  0), ( 1,  0), ( 1,  1), ( 0, -2), ( 1, -2)],
+    [( 0,  0), (-1,  0), (-1, -1), ( 0,  2), (-1,  2)],
+    [( 0,  0), (-1,  0), (-1, -1), ( 0,  2), (-1,  2)],
+    [( 0,  0), ( 1,  0), ( 1,  1), ( 0, -2), ( 1, -2)]
+];
+
+const KICKS_I: [[(i32, i32); 5]; 8] = [
+    [( 0,  0), (-2,  0), ( 1,  0), (-2, -1), ( 1,  2)],
+    [( 0,  0), ( 2,  0), (-1,  0), ( 2,  1), (-1, -2)],
+    [( 0,  0), (-1,  0), ( 2,  0), (-1,  2), ( 2, -1)],
+    [( 0,  0), ( 1,  0), (-2,  0), ( 1, -2), (-2,  1)],
+    [( 0,  0), ( 2,  0), (-1,  0), ( 2,  1), (-1, -2)],
+    [( 0,  0), (-2,  0), ( 1,  0), (-2, -1), ( 1,  2)],
+    [( 0,  0), ( 1,  0), (-2,  0), ( 1, -2), (-2,  1)],
+    [( 0,  0), (-1,  0), ( 2,  0), (-1,  2), ( 2, -1)]
+];
+
+// The guideline does not define 180 degrees kicks : these are the widespread
+// SRS+ ones, in the order 0->2, R->L, 2->0, L->R
+const KICKS_180: [[(i32, i32); 6]; 4] = [
+    [( 0,  0), ( 0,  1), ( 1,  1), (-1,  1), ( 1,  0), (-1,  0)],
+    [( 0,  0), ( 1,  0), ( 1,  2), ( 1,  1), ( 0,  2), ( 0,  1)],
+    [( 0,  0), ( 0, -1), (-1, -1), ( 1, -1), (-1,  0), ( 1,  0)],
+    [( 0,  0), (-1,  0), (-1,  2), (-1,  1), ( 0,  2), ( 0,  1)]
+];
+
+// Arika Rotation System kicks of TGM : in place, one column right, one
+// column left, for every rotation
+const KICKS_ARS: [(i32, i32); 3] = [(0, 0), (1, 0), (-1, 0)];
+
+const NO_KICK: [(i32, i32); 1] = [(0, 0)];
+
+/// Kicks a piece tests when rotating, named in piece set files
+#[derive(Clone, Copy, PartialEq)]
+pub enum KickTable {
+    /// Kicks of the J, L, S, T and Z tetrominos
+    Standard,
+    /// Kicks of the I tetromino
+    I,
+    /// One column right, then one column left, as the ARS of TGM
+    Ars,
+    /// Rotation in place only, as the O tetromino
+    None
+}
+
+impl KickTable {
+    pub fn from_name(name: &str) -> Result<KickTable, String> {
+        match name {
+            "srs" => Ok(KickTable::Standard),
+            "srs-i" => Ok(KickTable::I),
+            "ars" => Ok(KickTable::Ars),
+            "none" => Ok(KickTable::None),
+            _ => Err(format!("unknown kicks '{name}', expected srs, srs-i, ars or none"))
+        }
+    }
+
+    /// Returns the offsets to test, in order, when rotating from rotation
+    /// index `from` to rotation index `to`.
+    /// Offsets are converted to (i, j) grid offsets.
+    pub fn get_kicks(&self, from: usize, to: usize) -> Vec<(i32, i32)> {
+        let kicks: &[(i32, i32)] = match (self, get_transition_index(from, to)) {
+            (KickTable::None, _) => &NO_KICK,
+            (KickTable::Ars, _) => &KICKS_ARS,
+            (_, None) => &KICKS_180[from],
+            (KickTable::I, Some(transition)) => &KICKS_I[transition],
+            (KickTable::Standard, Some(transition)) => &KICKS_JLSTZ[transition]
+        };
+
+        kicks
+            .iter()
+            .map(|&(x, y)| (-y, x))
+            .collect()
+    }
+}
+
+/// Returns the line of the quarter turn kick tables matching the transition,
+/// or `None` for a half turn
+fn get_transition_index(from: usize, to: usize) -> Option<usize> {
+    match (from, to) {
+        (0, 1) => Some(0),
+        (1, 0) => Some(1),
+        (1, 2) => Some(2),
+        (2, 1) => Some(3),
+        (2, 3) => Some(4),
+        (3, 2) => Some(5),
+        (3, 0) => Some(6),
+        (0, 3) => Some(7),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quarter_turn_kicks_are_tested_in_guideline_order() {
+        // T 0->R : (0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2) in (x, y)
+        assert_eq!(
+            KickTable::Standard.get_kicks(0, 1),
+            vec![(0, 0), (0, -1), (-1, -1), (2, 0), (2, -1)]
+        );
+
+        // I R->2 : (0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1) in (x, y)
+        assert_eq!(
+            KickTable::I.get_kicks(1, 2),
+            vec![(0, 0), (0, -1), (0, 2), (-2, -1), (1, 2)]
+        );
+    }
+
+    #[test]
+    fn kicks_convert_x_right_y_up_to_row_column() {
+        for (from, to) in [(0, 1), (1, 0), (1, 2), (2, 1), (2, 3), (3, 2), (3, 0), (0, 3)] {
+            let transition = get_transition_index(from, to).unwrap();
+            let expected: Vec<(i32, i32)> = KICKS_JLSTZ[transition]
+                .iter()
+                .map(|&(x, y)| (-y, x))
+                .collect();
+
+            assert_eq!(KickTable::Standard.get_kicks(from, to), expected);
+        }
+    }
+
+    #[test]
+    fn half_turns_use_the_180_table() {
+        assert_eq!(get_transition_index(0, 2), None);
+        assert_eq!(KickTable::Standard.get_kicks(0, 2).len(), KICKS_180[0].len());
+        assert_eq!(KickTable::Standard.get_kicks(3, 1)[1], (0, -1));
+    }
+
+    #[test]
+    fn ars_kicks_right_then_left() {
+        assert_eq!(KickTable::Ars.get_kicks(0, 1), vec![(0, 0), (0, 1), (0, -1)]);
+        assert_eq!(KickTable::Ars.get_kicks(2, 1), vec![(0, 0), (0, 1), (0, -1)]);
+    }
+
+    #[test]
+    fn no_kick_rotates_in_place_only() {
+        assert_eq!(KickTable::None.get_kicks(0, 1), vec![(0, 0)]);
+        assert_eq!(KickTable::None.get_kicks(0, 2), vec![(0, 0)]);
+    }
+}
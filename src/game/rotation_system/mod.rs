@@ -0,0 +1,182 @@
+pub mod kicks;
+
+use crate::game::piece_set::{PieceDefinition, PieceSet};
+use crate::game::tetromino::Tetromino;
+
+const ARS_PIECES: &str = include_str!("../../../pieces/rotation/ars.txt");
+const NES_PIECES: &str = include_str!("../../../pieces/rotation/nes.txt");
+
+/// How pieces spawn, turn and get kicked out of the way when rotating : the
+/// shapes and kicks of each piece of the set
+pub struct RotationSystem {
+    /// Definition of each piece of the set, by piece id
+    pieces: Vec<PieceDefinition>
+}
+
+impl RotationSystem {
+    /// Reads the pieces of `piece_set` from the rotation file `text` of the
+    /// system `name`, written like a piece set file. Every piece must be
+    /// found by name.
+    fn read(name: &str, text: &str, piece_set: &PieceSet) -> Result<RotationSystem, String> {
+        let table = PieceSet::parse(text)
+            .map_err(|error| format!("Invalid rotation states of {name} : {error}"))?;
+
+        let pieces = piece_set
+            .get_pieces()
+            .iter()
+            .map(|piece| {
+                table
+                    .get_pieces()
+                    .iter()
+                    .find(|table_piece| table_piece.name == piece.name)
+                    .cloned()
+                    .ok_or(format!("Rotation system '{name}' has no piece '{}'", piece.name))
+            })
+            .collect::<Result<Vec<PieceDefinition>, String>>()?;
+
+        Ok(RotationSystem { pieces })
+    }
+
+    /// Returns the shapes and rotation rules of `tetromino`
+    fn get_definition(&self, tetromino: Tetromino) -> &PieceDefinition {
+        &self.pieces[tetromino.get_id()]
+    }
+
+    /// Returns the offsets (i, j) of the cells from the pivot in rotation
+    /// state `rotation`, state 0 being the spawn orientation. Pieces with
+    /// fewer than four states repeat them.
+    pub fn get_shape(&self, tetromino: Tetromino, rotation: usize) -> &[(i32, i32)] {
+        let shapes = &self.get_definition(tetromino).shapes;
+
+        &shapes[rotation % shapes.len()]
+    }
+
+    /// Returns the offsets (i, j) of the pivot to test, in order, when
+    /// rotating from rotation state `from` to rotation state `to`.
+    /// `is_blocked` tells if a cell, as an offset from the unkicked pivot,
+    /// is outside the board or full.
+    pub fn get_kicks(
+        &self,
+        tetromino: Tetromino,
+        from: usize,
+        to: usize,
+        is_blocked: &dyn Fn((i32, i32)) -> bool
+    ) -> Vec<(i32, i32)> {
+
+        let definition = self.get_definition(tetromino);
+
+        if definition.center_column && is_first_blocked_in_center(self.get_shape(tetromino, to), is_blocked) {
+            return vec![(0, 0)];
+        }
+
+        definition.kicks.get_kicks(from, to)
+    }
+
+    /// Checks if T-spins are detected for this piece
+    pub fn can_t_spin(&self, tetromino: Tetromino) -> bool {
+        self.get_definition(tetromino).t_spin
+    }
+}
+
+/// Rotation systems that can be selected at startup
+#[derive(Clone, Copy)]
+pub enum RotationSystemKind {
+    /// Super Rotation System of the guideline, with the shapes and kicks of
+    /// the piece set file
+    Srs,
+    /// Arika Rotation System of TGM
+    Ars,
+    /// Right-handed rotation of the NES version, without kicks
+    Nes
+}
+
+impl RotationSystemKind {
+    pub fn from_name(name: &str) -> Result<RotationSystemKind, String> {
+        match name {
+            "srs" => Ok(RotationSystemKind::Srs),
+            "ars" => Ok(RotationSystemKind::Ars),
+            "nes" => Ok(RotationSystemKind::Nes),
+            _ => Err(format!("Unknown rotation system '{name}', expected srs, ars or nes"))
+        }
+    }
+
+    /// Returns the rotation system for the pieces of `piece_set`
+    pub fn create(&self, piece_set: &PieceSet) -> Result<RotationSystem, String> {
+        match self {
+            RotationSystemKind::Srs => Ok(RotationSystem { pieces: piece_set.get_pieces().to_vec() }),
+            RotationSystemKind::Ars => RotationSystem::read("ars", ARS_PIECES, piece_set),
+            RotationSystemKind::Nes => RotationSystem::read("nes", NES_PIECES, piece_set)
+        }
+    }
+}
+
+/// Checks if the first blocked cell of `shape`, in reading order, is in the
+/// column of the pivot. TGM then fails the rotation of L, J and T instead of
+/// kicking them.
+fn is_first_blocked_in_center(shape: &[(i32, i32)], is_blocked: &dyn Fn((i32, i32)) -> bool) -> bool {
+    let mut cells = shape.to_vec();
+    cells.sort();
+
+    cells
+        .into_iter()
+        .find(|&cell| is_blocked(cell))
+        .is_some_and(|(_, j)| j == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::piece_set;
+
+    fn kicks_blocked_by(name: &str, blocked: &[(i32, i32)]) -> Vec<(i32, i32)> {
+        let tetrominos = piece_set::load("tetromino").unwrap();
+        let tetromino = tetrominos.find(name).unwrap();
+
+        match RotationSystemKind::Ars.create(&tetrominos) {
+            Ok(ars) => ars.get_kicks(tetromino, 0, 1, &|cell| blocked.contains(&cell)),
+            Err(error) => panic!("Cannot create ARS : {error}")
+        }
+    }
+
+    #[test]
+    fn rotation_files_know_every_tetromino_only() {
+        let tetrominos = piece_set::load("tetromino").unwrap();
+        let pentominos = piece_set::load("pentomino").unwrap();
+
+        for kind in [RotationSystemKind::Ars, RotationSystemKind::Nes] {
+            assert!(kind.create(&tetrominos).is_ok());
+
+            match kind.create(&pentominos) {
+                Ok(_) => panic!("Created a rotation system for pentominos"),
+                Err(error) => assert!(error.contains("has no piece"))
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_rotation_files_are_errors() {
+        let tetrominos = piece_set::load("tetromino").unwrap();
+
+        match RotationSystem::read("test", "piece T\n#x\n##\n", &tetrominos) {
+            Ok(_) => panic!("Read an invalid rotation file"),
+            Err(error) => {
+                assert!(error.starts_with("Invalid rotation states of test : "));
+                assert!(error.contains("unexpected 'x'"));
+            }
+        }
+    }
+
+    #[test]
+    fn ars_does_not_kick_l_j_t_blocked_in_the_center_column() {
+        // T 0->R needs the cells (-1, 0), (0, -1), (0, 0) and (1, 0)
+        assert_eq!(kicks_blocked_by("T", &[(-1, 0)]), vec![(0, 0)]);
+        assert_eq!(kicks_blocked_by("T", &[(-1, 0), (0, -1)]), vec![(0, 0)]);
+        assert_eq!(kicks_blocked_by("T", &[(0, -1), (1, 0)]).len(), 3);
+    }
+
+    #[test]
+    fn ars_kicks_other_pieces_blocked_in_the_center_column() {
+        assert_eq!(kicks_blocked_by("S", &[(0, 0)]).len(), 3);
+        assert_eq!(kicks_blocked_by("I", &[(-1, 0)]), vec![(0, 0)]);
+    }
+}
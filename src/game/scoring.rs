@@ -0,0 +1,252 @@
+use crate::game::cell::Cell;
+use crate::game::state::State;
+use crate::game::tetromino::RotationDirection;
+
+// Points of the guideline, multiplied by the level
+const EARNED_POINTS_SINGLE: u32 = 100;
+const EARNED_POINTS_DOUBLE: u32 = 300;
+const EARNED_POINTS_TRIPLE: u32 = 500;
+const EARNED_POINTS_TETRIS: u32 = 800;
+// Five lines at once, only possible with pentominos
+const EARNED_POINTS_PENTRIS: u32 = 1200;
+
+const EARNED_POINTS_T_SPIN_MINI: [u32; 3] = [100, 200, 400];
+const EARNED_POINTS_T_SPIN: [u32; 4] = [400, 800, 1200, 1600];
+
+const EARNED_POINTS_COMBO_STEP: u32 = 50;
+
+const EARNED_POINTS_PERFECT_CLEAR: [u32; 5] = [0, 800, 1200, 1800, 2000];
+const EARNED_POINTS_BACK_TO_BACK_TETRIS_PERFECT_CLEAR: u32 = 3200;
+
+// Index of the SRS quarter turn kick test that always makes a T-spin a
+// full one
+const T_SPIN_TRIPLE_KICK_INDEX: usize = 4;
+
+// Diagonal neighbours of the T pivot, and its orthogonal ones
+const T_CORNERS: [(i32, i32); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+const T_ARMS: [(i32, i32); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+/// Kick test that let the last rotation of the current tetromino succeed
+#[derive(Clone, Copy)]
+pub struct RotationKick {
+    pub direction: RotationDirection,
+    pub index: usize
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TSpin {
+    None,
+    Mini,
+    Full
+}
+
+/// What a lock achieved, used for scoring and displayed by the HUD
+#[derive(Clone, Copy)]
+pub struct ClearReport {
+    pub lines: u32,
+    pub spin: TSpin,
+    pub back_to_back: bool,
+    /// Number of consecutive clearing locks before this one
+    pub combo: u32,
+    pub perfect_clear: bool
+}
+
+impl ClearReport {
+    /// Clears that keep the back-to-back chain alive
+    pub fn is_difficult(&self) -> bool {
+        self.lines >= 4 || (self.spin != TSpin::None && self.lines > 0)
+    }
+
+    /// Returns the points earned at the given level
+    pub fn get_points(&self, level: u32) -> u32 {
+        let lines = self.lines as usize;
+
+        let mut points = match self.spin {
+            TSpin::None => match self.lines {
+                1 => EARNED_POINTS_SINGLE,
+                2 => EARNED_POINTS_DOUBLE,
+                3 => EARNED_POINTS_TRIPLE,
+                4 => EARNED_POINTS_TETRIS,
+                0 => 0,
+                _ => EARNED_POINTS_PENTRIS
+            },
+            TSpin::Mini => EARNED_POINTS_T_SPIN_MINI[lines.min(2)],
+            TSpin::Full => EARNED_POINTS_T_SPIN[lines.min(3)]
+        };
+
+        if self.back_to_back {
+            points = points * 3 / 2;
+        }
+
+        points += EARNED_POINTS_COMBO_STEP * self.combo;
+
+        if self.perfect_clear {
+            points += if self.back_to_back && self.lines >= 4 {
+                EARNED_POINTS_BACK_TO_BACK_TETRIS_PERFECT_CLEAR
+            } else {
+                EARNED_POINTS_PERFECT_CLEAR[lines.min(4)]
+            };
+        }
+
+        points * level
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match (self.spin, self.lines) {
+            (TSpin::None, 1) => "SINGLE",
+            (TSpin::None, 2) => "DOUBLE",
+            (TSpin::None, 3) => "TRIPLE",
+            (TSpin::None, 4) => "TETRIS",
+            (TSpin::None, _) => "PENTRIS",
+            (TSpin::Mini, 0) => "MINI T-SPIN",
+            (TSpin::Mini, 1) => "MINI T-SPIN SGL",
+            (TSpin::Mini, _) => "MINI T-SPIN DBL",
+            (TSpin::Full, 0) => "T-SPIN",
+            (TSpin::Full, 1) => "T-SPIN SINGLE",
+            (TSpin::Full, 2) => "T-SPIN DOUBLE",
+            (TSpin::Full, _) => "T-SPIN TRIPLE"
+        }
+    }
+}
+
+/// Returns the two diagonal neighbours of the pivot a T shape points to,
+/// next to its arm without an opposite one. None if the shape is not the
+/// pivot and three of its orthogonal neighbours.
+pub fn get_t_front_corners(shape: &[(i32, i32)]) -> Option<[(i32, i32); 2]> {
+    let arms = T_ARMS.iter().filter(|arm| shape.contains(arm)).count();
+
+    if shape.len() != 4 || !shape.contains(&(0, 0)) || arms != 3 {
+        return None;
+    }
+
+    let (i, j) = T_ARMS
+        .into_iter()
+        .find(|&(i, j)| shape.contains(&(i, j)) && !shape.contains(&(-i, -j)))?;
+
+    Some([(i + j, j + i), (i - j, j - i)])
+}
+
+impl State {
+    /// Detects a T-spin with the 3-corner rule : the T must have been
+    /// rotated last, with three of its four diagonal neighbours occupied.
+    /// It is a mini T-spin unless both corners the T points to are occupied
+    /// or a quarter turn used the T-spin triple kick.
+    pub fn detect_current_tetromino_spin(&self) -> TSpin {
+        let current_tetromino = self.get_current_tetromino_ref();
+
+        let kick = match self.get_last_rotation_kick() {
+            Some(kick) => kick,
+            None => return TSpin::None
+        };
+
+        if !self.get_rotation_system().can_t_spin(current_tetromino.get_tetromino()) {
+            return TSpin::None;
+        }
+
+        let occupied_corners = T_CORNERS
+            .iter()
+            .filter(|corner| self.is_occupied_around_current_tetromino(**corner))
+            .count();
+
+        if occupied_corners < 3 {
+            return TSpin::None;
+        }
+
+        let shape = current_tetromino.get_shape(self.get_rotation_system());
+
+        let front_corners = match get_t_front_corners(shape) {
+            Some(front_corners) => front_corners,
+            None => return TSpin::None
+        };
+
+        let is_t_spin_triple_kick = kick.direction != RotationDirection::Half
+            && kick.index == T_SPIN_TRIPLE_KICK_INDEX;
+
+        if is_t_spin_triple_kick
+            || front_corners.iter().all(|corner| self.is_occupied_around_current_tetromino(*corner)) {
+
+            TSpin::Full
+        } else {
+            TSpin::Mini
+        }
+    }
+
+    /// Checks if removing the full lines leaves the grid empty
+    pub fn is_perfect_clear(&self) -> bool {
+        let board = self.get_board();
+
+        (0..board.get_height()).all(|i| board.is_line_full(i) || board.is_line_empty(i))
+    }
+
+    /// Checks if the cell at `offset` from the current tetromino pivot is
+    /// full or outside the grid
+    fn is_occupied_around_current_tetromino(&self, offset: (i32, i32)) -> bool {
+        let pivot = self.get_current_tetromino_ref().get_position();
+        let i = pivot.i + offset.0;
+        let j = pivot.j + offset.1;
+
+        let board = self.get_board();
+
+        if i < 0 || j < 0 || i as usize >= board.get_height() || j as usize >= board.get_width() {
+            return true;
+        }
+
+        board.get_cell(i as usize, j as usize) != Cell::Empty
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_clear(lines: u32) -> ClearReport {
+        ClearReport {
+            lines,
+            spin: TSpin::None,
+            back_to_back: false,
+            combo: 0,
+            perfect_clear: false
+        }
+    }
+
+    #[test]
+    fn front_corners_follow_the_t() {
+        // Pointing up, right, down and left
+        let shapes = [
+            [(-1, 0), (0, -1), (0, 0), (0, 1)],
+            [(-1, 0), (0, 0), (0, 1), (1, 0)],
+            [(0, -1), (0, 0), (0, 1), (1, 0)],
+            [(-1, 0), (0, -1), (0, 0), (1, 0)]
+        ];
+        let front_corners = [
+            [(-1, -1), (-1, 1)],
+            [(-1, 1), (1, 1)],
+            [(1, -1), (1, 1)],
+            [(-1, -1), (1, -1)]
+        ];
+
+        for (shape, expected) in shapes.iter().zip(front_corners) {
+            let mut corners = get_t_front_corners(shape).unwrap();
+            corners.sort();
+
+            assert_eq!(corners, expected);
+        }
+    }
+
+    #[test]
+    fn other_shapes_have_no_front_corners() {
+        // L, I, and a T whose pivot is its end
+        assert_eq!(get_t_front_corners(&[(-1, 1), (0, -1), (0, 0), (0, 1)]), None);
+        assert_eq!(get_t_front_corners(&[(0, -1), (0, 0), (0, 1), (0, 2)]), None);
+        assert_eq!(get_t_front_corners(&[(0, 0), (1, -1), (1, 0), (1, 1)]), None);
+    }
+
+    #[test]
+    fn five_lines_outscore_a_tetris() {
+        let pentris = line_clear(5);
+
+        assert_eq!(pentris.get_name(), "PENTRIS");
+        assert!(pentris.is_difficult());
+        assert!(pentris.get_points(1) > line_clear(4).get_points(1));
+    }
+}
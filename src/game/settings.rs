@@ -0,0 +1,326 @@
+use std::str::FromStr;
+
+use crate::game::{self, board};
+use crate::game::garbage::GarbageHoles;
+use crate::game::gravity::GravityCurve;
+use crate::game::hold::HoldRule;
+use crate::game::modes::ModeKind;
+use crate::game::piece_set::PieceSet;
+use crate::game::randomizer::RandomizerKind;
+use crate::game::rotation_system::RotationSystemKind;
+use crate::game::stack_visibility::StackVisibility;
+
+const USAGE: &str = "Usage : tetris [--mode endless|marathon|sprint|ultra|zen|dig|master]\n        [--randomizer 7-bag|14-bag|history|nes|random] [--seed <number>]\n        [--lock-delay <ms>] [--lock-resets <number>]\n        [--gravity guideline|nes|tgm|custom:<G>,<G>,...]\n        [--line-clear-delay <ms>] [--entry-delay <ms>]\n        [--das <ms>] [--arr <ms>] [--sdf <factor>]\n        [--width <columns>] [--height <rows>] [--previews <number>]\n        [--hold none|guideline|unlimited]\n        [--garbage <rows>] [--garbage-holes random|column:<index>|messy:<percentage>]\n        [--garbage-interval <ms>] [--stack visible|invisible|fade:<ms>] [--big]\n        [--pieces tetromino|pentomino|triomino|<file>] [--rotation srs|ars|nes]";
+
+/// Blocks drawn per board cell in each direction in big mode
+const BIG_BLOCK_SIZE: usize = 2;
+
+/// Game options chosen at startup from the command line
+pub struct Settings {
+    pub mode: ModeKind,
+    pub randomizer: RandomizerKind,
+    /// Seed of the piece sequence, drawn at random when not given
+    pub seed: Option<u64>,
+    pub lock_delay_millis: u64,
+    /// Moves and rotations allowed to restart the lock delay of one tetromino
+    pub lock_resets: u32,
+    pub gravity_curve: GravityCurve,
+    /// Time full lines stay on the grid before being removed
+    pub line_clear_delay_millis: u64,
+    /// Time between a lock or a line clear and the next spawn, known as ARE
+    pub entry_delay_millis: u64,
+    /// Time a shift key is held before it starts repeating, known as DAS
+    pub das_millis: u64,
+    /// Time between two repeats of a held shift key, known as ARR.
+    /// At 0, held keys shift straight to the wall.
+    pub arr_millis: u64,
+    /// Multiplier of the gravity while the soft drop key is held
+    pub soft_drop_factor: u64,
+    pub board_width: usize,
+    /// Visible rows of the board, the vanish zone above is as high
+    pub board_height: usize,
+    /// Length of the next queue shown in the NEXT section
+    pub previews: usize,
+    pub hold_rule: HoldRule,
+    /// Garbage rows on the board at the start of the game, the mode
+    /// default when not given
+    pub garbage_rows: Option<usize>,
+    pub garbage_holes: GarbageHoles,
+    /// Time between two garbage rows pushed in by the dig mode, 0 for none
+    pub garbage_interval_millis: u64,
+    pub stack_visibility: StackVisibility,
+    /// Big mode : every cell is drawn as 2x2 blocks, the board keeping the
+    /// same size on screen with half the columns and rows
+    pub big: bool,
+    /// Name of a built-in piece set, or path of a piece set file
+    pub pieces: String,
+    pub rotation_system: RotationSystemKind
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            mode: ModeKind::Endless,
+            randomizer: RandomizerKind::SevenBag,
+            seed: None,
+            lock_delay_millis: 500,
+            lock_resets: 15,
+            gravity_curve: GravityCurve::Guideline,
+            line_clear_delay_millis: 250,
+            entry_delay_millis: 100,
+            das_millis: 170,
+            arr_millis: 50,
+            soft_drop_factor: 20,
+            board_width: 10,
+            board_height: 20,
+            previews: 3,
+            hold_rule: HoldRule::OncePerPiece,
+            garbage_rows: None,
+            garbage_holes: GarbageHoles::Messy(30),
+            garbage_interval_millis: 0,
+            stack_visibility: StackVisibility::Visible,
+            big: false,
+            pieces: String::from("tetromino"),
+            rotation_system: RotationSystemKind::Srs
+        }
+    }
+}
+
+impl Settings {
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Result<Settings, String> {
+        let mut settings = Settings::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--mode" => {
+                    settings.mode = ModeKind::from_name(&next_value(&mut args, &arg)?)?
+                },
+                "--randomizer" => {
+                    settings.randomizer = RandomizerKind::from_name(&next_value(&mut args, &arg)?)?
+                },
+                "--seed" => {
+                    settings.seed = Some(next_parsed_value(&mut args, &arg)?)
+                },
+                "--lock-delay" => {
+                    settings.lock_delay_millis = next_parsed_value(&mut args, &arg)?
+                },
+                "--lock-resets" => {
+                    settings.lock_resets = next_parsed_value(&mut args, &arg)?
+                },
+                "--gravity" => {
+                    settings.gravity_curve = GravityCurve::from_name(&next_value(&mut args, &arg)?)?
+                },
+                "--line-clear-delay" => {
+                    settings.line_clear_delay_millis = next_parsed_value(&mut args, &arg)?
+                },
+                "--entry-delay" => {
+                    settings.entry_delay_millis = next_parsed_value(&mut args, &arg)?
+                },
+                "--das" => {
+                    settings.das_millis = next_parsed_value(&mut args, &arg)?
+                },
+                "--arr" => {
+                    settings.arr_millis = next_parsed_value(&mut args, &arg)?
+                },
+                "--sdf" => {
+                    settings.soft_drop_factor = next_parsed_value(&mut args, &arg)?;
+
+                    if settings.soft_drop_factor == 0 {
+                        return Err(format!("Option '{arg}' must be at least 1\n{USAGE}"));
+                    }
+                },
+                "--width" => {
+                    settings.board_width = next_bounded_value(
+                        &mut args, &arg, board::MIN_WIDTH, board::MAX_WIDTH
+                    )?
+                },
+                "--height" => {
+                    settings.board_height = next_bounded_value(
+                        &mut args, &arg, board::MIN_VISIBLE_HEIGHT, board::MAX_VISIBLE_HEIGHT
+                    )?
+                },
+                "--previews" => {
+                    settings.previews = next_bounded_value(
+                        &mut args, &arg, 0, game::MAX_NEXT_TETROMINOS_QUEUE_SIZE
+                    )?
+                },
+                "--hold" => {
+                    settings.hold_rule = HoldRule::from_name(&next_value(&mut args, &arg)?)?
+                },
+                "--garbage" => {
+                    settings.garbage_rows = Some(next_parsed_value(&mut args, &arg)?)
+                },
+                "--garbage-holes" => {
+                    settings.garbage_holes = GarbageHoles::from_name(&next_value(&mut args, &arg)?)?
+                },
+                "--garbage-interval" => {
+                    settings.garbage_interval_millis = next_parsed_value(&mut args, &arg)?
+                },
+                "--stack" => {
+                    settings.stack_visibility = StackVisibility::from_name(&next_value(&mut args, &arg)?)?
+                },
+                "--big" => {
+                    settings.big = true
+                },
+                "--pieces" => {
+                    settings.pieces = next_value(&mut args, &arg)?
+                },
+                "--rotation" => {
+                    settings.rotation_system = RotationSystemKind::from_name(&next_value(&mut args, &arg)?)?
+                },
+                _ => return Err(format!("Unknown option '{arg}'\n{USAGE}"))
+            }
+        }
+
+        settings.check_big_board()?;
+        settings.check_garbage()?;
+
+        Ok(settings)
+    }
+}
+
+impl Settings {
+    /// Returns the blocks drawn per board cell in each direction
+    pub fn get_block_size(&self) -> usize {
+        if self.big { BIG_BLOCK_SIZE } else { 1 }
+    }
+
+    /// Returns the columns of the board, halved in big mode
+    pub fn get_board_width(&self) -> usize {
+        self.board_width / self.get_block_size()
+    }
+
+    /// Returns the visible rows of the board, halved in big mode
+    pub fn get_board_height(&self) -> usize {
+        self.board_height / self.get_block_size()
+    }
+
+    /// Returns the garbage rows given, or the default rows of the mode
+    /// covering the same part of the board in big mode and leaving at least
+    /// one row free
+    /// Returns the ARR in frames : 0 only for an ARR of 0 milliseconds,
+    /// shorter ones than a frame repeating every frame
+    pub fn get_arr_frames(&self) -> u128 {
+        match self.arr_millis {
+            0 => 0,
+            millis => game::millis_to_frames(millis).max(1)
+        }
+    }
+
+    pub fn get_garbage_rows(&self) -> usize {
+        self.garbage_rows.unwrap_or_else(|| {
+            (self.mode.get_default_garbage_rows() / self.get_block_size())
+                .min(self.get_board_height() - 1)
+        })
+    }
+
+    /// Returns the gravity curve of the mode, or the chosen one if the mode
+    /// has none
+    pub fn get_gravity_curve(&self) -> GravityCurve {
+        self.mode.get_gravity_curve().unwrap_or_else(|| self.gravity_curve.clone())
+    }
+
+    /// Checks that the board keeps its minimum size once halved in big mode
+    fn check_big_board(&self) -> Result<(), String> {
+        if self.get_board_width() < board::MIN_WIDTH
+            || self.get_board_height() < board::MIN_VISIBLE_HEIGHT {
+
+            return Err(format!(
+                "Option '--big' needs a board of at least {} columns and {} rows\n{USAGE}",
+                board::MIN_WIDTH * BIG_BLOCK_SIZE,
+                board::MIN_VISIBLE_HEIGHT * BIG_BLOCK_SIZE
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that every rotation state of the pieces fits in the board
+    pub fn check_piece_set(&self, piece_set: &PieceSet) -> Result<(), String> {
+        if piece_set.get_max_width() > self.get_board_width() {
+            return Err(format!(
+                "The pieces of set '{}' need a board of at least {} columns\n{USAGE}",
+                self.pieces,
+                piece_set.get_max_width() * self.get_block_size()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Checks the garbage options against the board size, which may be given
+    /// after them
+    fn check_garbage(&self) -> Result<(), String> {
+        if matches!(self.mode, ModeKind::Dig) && self.get_garbage_rows() == 0 {
+            return Err(format!("Option '--garbage' must be at least 1 in dig mode\n{USAGE}"));
+        }
+
+        if self.get_garbage_rows() >= self.get_board_height() {
+            let halved = if self.big { ", halved in big mode" } else { "" };
+
+            return Err(format!(
+                "Option '--garbage' must be lower than the board height ({}{halved})\n{USAGE}",
+                self.get_board_height()
+            ));
+        }
+
+        if let GarbageHoles::Column(column) = self.garbage_holes
+            && column >= self.get_board_width() {
+
+            return Err(format!(
+                "Garbage hole column must be lower than the board width ({})\n{USAGE}",
+                self.get_board_width()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+fn next_value(args: &mut impl Iterator<Item = String>, option: &str) -> Result<String, String> {
+    match args.next() {
+        Some(value) => Ok(value),
+        None => Err(format!("Missing value for option '{option}'\n{USAGE}"))
+    }
+}
+
+fn next_parsed_value<T: FromStr>(
+    args: &mut impl Iterator<Item = String>,
+    option: &str
+) -> Result<T, String> {
+    let value = next_value(args, option)?;
+
+    match value.parse() {
+        Ok(parsed) => Ok(parsed),
+        Err(_) => Err(format!("Invalid value '{value}' for option '{option}'\n{USAGE}"))
+    }
+}
+
+fn next_bounded_value(
+    args: &mut impl Iterator<Item = String>,
+    option: &str,
+    min: usize,
+    max: usize
+) -> Result<usize, String> {
+    let value = next_parsed_value(args, option)?;
+
+    if value < min || value > max {
+        return Err(format!("Option '{option}' must be between {min} and {max}\n{USAGE}"));
+    }
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_zero_arr_shifts_to_the_wall() {
+        for (arr_millis, arr_frames) in [(0, 0), (1, 1), (9, 1), (10, 1), (25, 2), (50, 5)] {
+            let settings = Settings { arr_millis, ..Settings::default() };
+
+            assert_eq!(settings.get_arr_frames(), arr_frames, "{arr_millis} ms");
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use crate::game;
+
+/// How long locked cells stay on screen. Hidden cells still collide, and
+/// the whole stack is revealed once the game is over.
+#[derive(Clone, Copy, PartialEq)]
+pub enum StackVisibility {
+    Visible,
+    /// Cells fade out of view the given milliseconds after locking
+    Fading(u64),
+    /// Cells disappear as soon as they lock
+    Invisible
+}
+
+impl StackVisibility {
+    /// Parses `visible`, `invisible` or `fade:<ms>`
+    pub fn from_name(name: &str) -> Result<StackVisibility, String> {
+        match name {
+            "visible" => Ok(StackVisibility::Visible),
+            "invisible" => Ok(StackVisibility::Invisible),
+            _ => match name.strip_prefix("fade:").map(|millis| millis.parse()) {
+                Some(Ok(millis)) => Ok(StackVisibility::Fading(millis)),
+                _ => Err(format!("Unknown stack visibility '{name}', expected visible, invisible or fade:<ms>"))
+            }
+        }
+    }
+
+    /// Checks if a cell locked at `lock_clock` is still shown at `clock`
+    pub fn is_cell_shown(&self, lock_clock: u128, clock: u128) -> bool {
+        match self {
+            StackVisibility::Visible => true,
+            StackVisibility::Fading(millis) => clock - lock_clock < game::millis_to_frames(*millis),
+            StackVisibility::Invisible => false
+        }
+    }
+}
@@ -1,7 +1,24 @@
 use crate::game;
-use game::cell::Cell;
+use game::board::Board;
+use game::gravity::GravityCurve;
+use game::level::SectionRecord;
+use game::lock_delay::LockDelay;
+use game::auto_repeat::AutoRepeat;
+use game::hold::HoldRule;
+use game::modes::{Delays, GameMode};
+use game::piece_set::PieceSet;
+use game::initial_actions::InitialActions;
+use game::phase::Phase;
+use game::scoring::{ClearReport, RotationKick};
 use game::tetromino::Tetromino;
-use rand::{self, Rng};
+use game::top_out::TopOut;
+use game::randomizer::Randomizer;
+use game::rotation_system::RotationSystem;
+use game::settings::Settings;
+use game::stack_visibility::StackVisibility;
+use rand::{Rng, SeedableRng};
+use std::collections::VecDeque;
+use rand_chacha::ChaCha8Rng;
 
 #[derive(Clone, Copy)]
 pub struct GridCoords {
@@ -13,15 +30,20 @@ impl GridCoords {
     pub fn equals(&self, other: &GridCoords) -> bool {
         self.i == other.i && self.j == other.j
     }
+}
 
-    pub fn to_grid_index(&self) -> usize {
-        self.i * game::GRID_WIDTH + self.j
-    }
+/// Coordinates of the pivot of the current tetromino. Depending on the
+/// rotation, the pivot may lie outside the grid while all cells are inside.
+#[derive(Clone, Copy)]
+pub struct PivotCoords {
+    pub i: i32,
+    pub j: i32
 }
 
+#[derive(Clone, Copy)]
 pub struct CurrentTetromino {
     tetromino: Tetromino,
-    position: GridCoords,
+    position: PivotCoords,
     rotation: usize
 }
 
@@ -30,7 +52,7 @@ impl CurrentTetromino {
         self.tetromino
     }
 
-    pub fn get_position(&self) -> GridCoords {
+    pub fn get_position(&self) -> PivotCoords {
         self.position
     }
 
@@ -40,90 +62,193 @@ impl CurrentTetromino {
 }
 
 impl CurrentTetromino {
-    /** Move the current tetromino position one cell to the left.
-     *  Panics if current tetromino j coord is already 0
-     */
+    /// Move the current tetromino position one cell to the left.
     pub fn move_left(&mut self) {
-        if self.position.j == 0 {
-            panic!("Tried to move left current tetromino while it was already at column j = 0");
-        }
         self.position.j -= 1
     }
 
-    /** Move the current tetromino position one cell to the right.
-     *  Panics if current tetromino j coord is already GRID_WIDTH - 1
-     */
+    /// Move the current tetromino position one cell to the right.
     pub fn move_right(&mut self) {
-        if self.position.j + 1 == game::GRID_WIDTH {
-            panic!("Tried to move right current tetromino while it was already at column j = GRID_WIDTH - 1");
-        }
         self.position.j += 1
     }
 
-    /** Move the current tetromino position one cell downwards.
-     *  Panics if current tetromino i coord is already GRID_HEIGHT - 1
-     */
+    /// Move the current tetromino position one cell upwards.
+    pub fn move_up(&mut self) {
+        self.position.i -= 1
+    }
+
+    /** Move the current tetromino position one cell downwards. */
     pub fn move_down(&mut self) {
-        if self.position.i + 1 == game::GRID_HEIGHT {
-            panic!("Tried to move down current tetromino while it was already at row i = GRID_HEIGHT - 1");
-        }
         self.position.i += 1
     }
 
-    /** Move the current tetromino position one cell upwards.
-     *  Panics if current tetromino i coord is already 0
-     */
-    pub fn move_up(&mut self) {
-        if self.position.i  == 0 {
-            panic!("Tried to move up current tetromino while it was already at row i = 0");
+    /// Returns this tetromino with rotation `rotation`, its pivot moved by
+    /// `offset` (i, j)
+    pub fn rotated(&self, rotation: usize, offset: (i32, i32)) -> CurrentTetromino {
+        CurrentTetromino {
+            tetromino: self.tetromino,
+            position: PivotCoords {
+                i: self.position.i + offset.0,
+                j: self.position.j + offset.1
+            },
+            rotation
         }
-        self.position.i -= 1
     }
 }
 
 pub struct State {
-    pub grid: [Cell; game::GRID_LENGTH],
+    board: Board,
+    /// Blocks drawn per cell in each direction, 2 in big mode
+    block_size: usize,
     current_tetromino: CurrentTetromino,
     hold: Option<Tetromino>,
+    hold_rule: HoldRule,
     can_hold: bool,
-    next_tetrominos_queue: [Tetromino; game::NEXT_TETROMINOS_QUEUE_SIZE],
+    next_tetrominos_queue: VecDeque<Tetromino>,
     score: u32,
     level: u32,
+    /// Completed sections of levels, under the level by piece progression
+    sections: Vec<SectionRecord>,
     lines: u32,
     clock: u128,
-    rng: rand::rngs::ThreadRng,
-    paused: bool
+    pieces: u32,
+    mode: Box<dyn GameMode>,
+    seed: u64,
+    rng: ChaCha8Rng,
+    /// Separate generator for garbage, which leaves the piece sequence of a
+    /// seed unchanged
+    garbage_rng: ChaCha8Rng,
+    last_garbage_hole: Option<usize>,
+    piece_set: PieceSet,
+    randomizer: Box<dyn Randomizer>,
+    rotation_system: RotationSystem,
+    gravity_curve: GravityCurve,
+    /// Fraction of cell fallen since the last row, in 1/65536 cell
+    gravity_progress: u64,
+    lock_delay: LockDelay,
+    auto_repeat: AutoRepeat,
+    initial_actions: InitialActions,
+    phase: Phase,
+    line_clear_delay: u128,
+    entry_delay: u128,
+    last_rotation_kick: Option<RotationKick>,
+    back_to_back: bool,
+    combo: Option<u32>,
+    last_clear: Option<ClearReport>,
+    stack_visibility: StackVisibility,
+    paused: bool,
+    top_out: Option<TopOut>,
+    goal_reached: bool
 }
 
-impl State {
-    pub fn new() -> State {
-        let mut rng = rand::rng();
+const GARBAGE_RNG_STREAM: u64 = 1;
 
-        State {
-            grid: [Cell::Empty; game::GRID_LENGTH],
+impl State {
+    /// Returns a new game of the pieces of `piece_set`, fails if the rotation
+    /// system of the settings does not know them
+    pub fn new(settings: &Settings, piece_set: PieceSet) -> Result<State, String> {
+        let seed = match settings.seed {
+            Some(seed) => seed,
+            None => rand::rng().random()
+        };
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut garbage_rng = ChaCha8Rng::seed_from_u64(seed);
+        garbage_rng.set_stream(GARBAGE_RNG_STREAM);
+        let rotation_system = settings.rotation_system.create(&piece_set)?;
+        let mut randomizer = settings.randomizer.create(&piece_set);
+
+        let first_tetromino = randomizer.next_tetromino(&mut rng);
+        let next_tetrominos_queue = (0..settings.previews)
+            .map(|_| randomizer.next_tetromino(&mut rng))
+            .collect();
+
+        let mode = settings.mode.create(settings);
+        let start_level = mode.get_level_progress().get_start_level();
+
+        let mut state = State {
+            board: Board::new(settings.get_board_width(), settings.get_board_height()),
+            block_size: settings.get_block_size(),
             current_tetromino: CurrentTetromino {
-                tetromino: Tetromino::J,
-                position: GridCoords { i: 1, j: 4 },
+                tetromino: first_tetromino,
+                position: PivotCoords { i: 0, j: 0 },
                 rotation: 0
             },
             hold: None,
+            hold_rule: settings.hold_rule,
             can_hold: true,
-            next_tetrominos_queue: [
-                Tetromino::from_index(rng.random()),
-                Tetromino::from_index(rng.random()),
-                Tetromino::from_index(rng.random())
-            ],
+            next_tetrominos_queue,
             score: 0,
-            level: 1,
+            level: start_level,
+            sections: Vec::new(),
             lines: 0,
             clock: 0,
-            rng: rand::rng(),
-            paused: false
-        }
+            pieces: 0,
+            mode,
+            seed,
+            rng,
+            garbage_rng,
+            last_garbage_hole: None,
+            piece_set,
+            randomizer,
+            rotation_system,
+            gravity_curve: settings.get_gravity_curve(),
+            gravity_progress: 0,
+            lock_delay: LockDelay::new(
+                game::millis_to_frames(settings.lock_delay_millis),
+                settings.lock_resets
+            ),
+            auto_repeat: AutoRepeat::new(
+                game::millis_to_frames(settings.das_millis),
+                settings.get_arr_frames(),
+                settings.soft_drop_factor
+            ),
+            initial_actions: InitialActions::default(),
+            phase: Phase::Falling,
+            line_clear_delay: game::millis_to_frames(settings.line_clear_delay_millis),
+            entry_delay: game::millis_to_frames(settings.entry_delay_millis),
+            last_rotation_kick: None,
+            back_to_back: false,
+            combo: None,
+            last_clear: None,
+            stack_visibility: settings.stack_visibility,
+            paused: false,
+            top_out: None,
+            goal_reached: false
+        };
+
+        state.update_level_delays();
+        state.set_new_current_tetromino(first_tetromino);
+        state.add_garbage_rows(settings.get_garbage_rows(), settings.garbage_holes);
+
+        Ok(state)
     }
 
-    pub fn get_grid_cell(&self, i: usize, j: usize ) -> Cell {
-        self.grid[i * game::GRID_WIDTH + j]
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn get_board_mutref(&mut self) -> &mut Board {
+        &mut self.board
+    }
+
+    pub fn get_piece_set(&self) -> &PieceSet {
+        &self.piece_set
+    }
+
+    pub fn get_rotation_system(&self) -> &RotationSystem {
+        &self.rotation_system
+    }
+
+    pub fn get_block_size(&self) -> usize {
+        self.block_size
+    }
+
+    pub fn get_garbage_rng_mutref(&mut self) -> &mut ChaCha8Rng {
+        &mut self.garbage_rng
+    }
+
+    pub fn get_last_garbage_hole(&self) -> Option<usize> {
+        self.last_garbage_hole
     }
 
     pub fn get_current_tetromino_ref(&self) -> &CurrentTetromino {
@@ -134,6 +259,48 @@ impl State {
         &mut self.current_tetromino
     }
 
+    pub fn get_gravity_curve(&self) -> &GravityCurve {
+        &self.gravity_curve
+    }
+
+    pub fn get_gravity_progress(&self) -> u64 {
+        self.gravity_progress
+    }
+
+    pub fn get_lock_delay_mutref(&mut self) -> &mut LockDelay {
+        &mut self.lock_delay
+    }
+
+    pub fn get_auto_repeat_ref(&self) -> &AutoRepeat {
+        &self.auto_repeat
+    }
+
+    pub fn get_auto_repeat_mutref(&mut self) -> &mut AutoRepeat {
+        &mut self.auto_repeat
+    }
+
+    pub fn get_initial_actions_mutref(&mut self) -> &mut InitialActions {
+        &mut self.initial_actions
+    }
+
+    pub fn get_phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Checks if the current tetromino is on the grid, which is not the case
+    /// during line clear and entry delays
+    pub fn is_tetromino_falling(&self) -> bool {
+        self.phase == Phase::Falling
+    }
+
+    pub fn get_line_clear_delay(&self) -> u128 {
+        self.line_clear_delay
+    }
+
+    pub fn get_entry_delay(&self) -> u128 {
+        self.entry_delay
+    }
+
     pub fn get_current_tetromino(&self) -> Tetromino {
         self.current_tetromino.tetromino
     }
@@ -142,14 +309,26 @@ impl State {
         self.hold
     }
 
+    pub fn get_hold_rule(&self) -> HoldRule {
+        self.hold_rule
+    }
+
     pub fn can_store(&self) -> bool {
-        self.can_hold
+        match self.hold_rule {
+            HoldRule::Disabled => false,
+            HoldRule::OncePerPiece => self.can_hold,
+            HoldRule::Unlimited => true
+        }
     }
 
     pub fn get_in_next_tetromino_queue(&self, index: usize) -> Tetromino {
         self.next_tetrominos_queue[index]
     }
 
+    pub fn get_next_tetrominos_queue_length(&self) -> usize {
+        self.next_tetrominos_queue.len()
+    }
+
     pub fn get_score(&self) -> u32 {
         self.score
     }
@@ -158,40 +337,99 @@ impl State {
         self.level
     }
 
+    pub fn get_section_records(&self) -> &[SectionRecord] {
+        &self.sections
+    }
+
     pub fn get_lines(&self) -> u32 {
         self.lines
     }
 
-    pub fn get_clock(&self) -> u128 {
-        self.clock
+    /// Returns the index of the kick used by the last successful move, if
+    /// it was a rotation
+    pub fn get_last_rotation_kick(&self) -> Option<RotationKick> {
+        self.last_rotation_kick
+    }
+
+    pub fn is_back_to_back_ready(&self) -> bool {
+        self.back_to_back
+    }
+
+    /// Returns the number of consecutive clearing locks minus one, or None
+    /// if the last lock did not clear any line
+    pub fn get_combo(&self) -> Option<u32> {
+        self.combo
+    }
+
+    pub fn get_last_clear(&self) -> Option<ClearReport> {
+        self.last_clear
+    }
+
+    pub fn get_stack_visibility(&self) -> StackVisibility {
+        self.stack_visibility
+    }
+
+    /// Returns the seed of the random number generator, which is enough
+    /// to replay the same piece sequence
+    pub fn get_seed(&self) -> u64 {
+        self.seed
     }
 
     pub fn is_game_paused(&self) -> bool {
         self.paused
     }
 
-    pub fn is_grid_line_full(&self, i: usize) -> bool {
-        for j in 0..game::GRID_WIDTH {
-            if self.grid[i * game::GRID_WIDTH + j] == Cell::Empty {
-                return false;
-            }
-        }
+    /// Checks if the game ended, either topped out or with the goal of the
+    /// mode reached
+    pub fn is_game_over(&self) -> bool {
+        self.top_out.is_some() || self.goal_reached
+    }
 
-        true
+    pub fn is_goal_reached(&self) -> bool {
+        self.goal_reached
     }
+
+    pub fn get_mode(&self) -> &dyn GameMode {
+        self.mode.as_ref()
+    }
+
+    /// Returns the number of frames played, pauses excluded
+    pub fn get_clock(&self) -> u128 {
+        self.clock
+    }
+
+    /// Returns the number of tetrominos locked
+    pub fn get_pieces(&self) -> u32 {
+        self.pieces
+    }
+
 }
 
 impl State {
-    pub fn set_grid_cell(&mut self, i: usize, j: usize, cell: Cell) {
-        self.grid[i * game::GRID_WIDTH + j] = cell
+    pub fn set_last_garbage_hole(&mut self, column: Option<usize>) {
+        self.last_garbage_hole = column
     }
 
-    pub fn increment_level(&mut self) {
-        self.level += 1;
+    /// Tells the engine whether the terminal reports key releases, which
+    /// decides how held keys are handled
+    pub fn set_key_release_events_flag(&mut self, value: bool) {
+        self.auto_repeat.set_enabled_flag(value);
+        self.initial_actions.set_release_events_flag(value);
     }
 
-    pub fn increment_rotation(&mut self) {
-        self.current_tetromino.rotation = (self.current_tetromino.rotation + 1) % 4;
+    pub fn set_level(&mut self, level: u32) {
+        self.level = level
+    }
+
+    pub fn add_section_record(&mut self, record: SectionRecord) {
+        self.sections.push(record)
+    }
+
+    /// Replaces the lock, line clear and entry delays
+    pub fn set_delays(&mut self, delays: Delays) {
+        self.lock_delay.set_duration(delays.lock);
+        self.line_clear_delay = delays.line_clear;
+        self.entry_delay = delays.entry;
     }
 
     pub fn increment_clock(&mut self) {
@@ -199,38 +437,48 @@ impl State {
     }
 
     pub fn pop_tetromino_queue(&mut self) -> Tetromino {
-        let tetromino = self.next_tetrominos_queue[0];
-        self.next_tetrominos_queue[0] = self.next_tetrominos_queue[1];
-        self.next_tetrominos_queue[1] = self.next_tetrominos_queue[2];
-        self.next_tetrominos_queue[2] = self.get_random_tetromino();
+        let new_tetromino = self.get_random_tetromino();
+        self.next_tetrominos_queue.push_back(new_tetromino);
 
-        tetromino
+        // Without preview, the tetromino just drawn comes right back out
+        self.next_tetrominos_queue.pop_front().unwrap_or(new_tetromino)
     }
 
     pub fn set_new_current_tetromino(&mut self, new_tetromino: Tetromino) {
+        let spawn_position = self.board.get_spawn_position();
+
         self.current_tetromino = CurrentTetromino {
             tetromino: new_tetromino,
-            position: GridCoords { i: 1, j: 4 },
+            position: spawn_position,
             rotation: 0
         };
+        self.lock_delay.restart(spawn_position.i);
+        self.gravity_progress = 0;
+        self.phase = Phase::Falling;
+        self.last_rotation_kick = None;
+
+        if self.is_current_tetromino_blocked_out() {
+            self.set_top_out(TopOut::BlockOut);
+            return;
+        }
+
+        // As in the guideline, the tetromino drops one row into the visible
+        // field right away if nothing is in its way
+        if self.can_current_tetromino_move_down() {
+            self.current_tetromino.move_down();
+            self.lock_delay.restart(spawn_position.i + 1);
+        }
     }
 
     pub fn set_next_tetromino_to_current(&mut self) {
-        self.current_tetromino = CurrentTetromino {
-            tetromino: self.pop_tetromino_queue(),
-            position: GridCoords { i: 1, j: 4 },
-            rotation: 0
-        };
+        let new_tetromino = self.pop_tetromino_queue();
+        self.set_new_current_tetromino(new_tetromino);
 
         self.set_can_store_flag(true);
     }
 
     fn get_random_tetromino(&mut self) -> Tetromino {
-        Tetromino::from_index(self.get_random_u32())
-    }
-
-    fn get_random_u32(&mut self) -> u32 {
-        self.rng.random::<u32>()
+        self.randomizer.next_tetromino(&mut self.rng)
     }
 
     pub fn set_stored_tetromino(&mut self, tetromino: Option<Tetromino>) {
@@ -241,43 +489,108 @@ impl State {
         self.can_hold = value
     }
 
-    pub fn clear_grid_line(&mut self, i: usize) {
-        for j in 0..game::GRID_WIDTH {
-            self.set_grid_cell(i, j, Cell::Empty);
-        }
+    pub fn add_to_lines(&mut self, amount: u32) {
+        self.lines += amount
+    }
 
-        self.lines += 1
+    pub fn add_to_score(&mut self, amount: u32) {
+        self.score += amount
     }
 
-    pub fn shift_tetromino_cells_down(&mut self, row_limit: usize) {
-        if row_limit == 0 {
-            panic!("Tried to shift tetromino cells down with row_limit = 0");
-        }
+    pub fn flip_paused_flag(&mut self) {
+        self.paused = !self.paused
+    }
 
-        for j in 0..game::GRID_WIDTH {
-            self.shift_tetromino_column_down(row_limit, j);
-        }
+    pub fn set_phase(&mut self, phase: Phase) {
+        self.phase = phase
     }
 
-    fn shift_tetromino_column_down(&mut self, row_limit: usize, j: usize) {
-        let mut i = row_limit - 1;
-        let mut cell = self.get_grid_cell(i, j);
+    pub fn set_gravity_progress(&mut self, progress: u64) {
+        self.gravity_progress = progress
+    }
+
+    pub fn set_last_rotation_kick(&mut self, kick: Option<RotationKick>) {
+        self.last_rotation_kick = kick
+    }
 
-        while i > 0 {
-            self.set_grid_cell(i + 1, j, cell);
-            i -= 1;
-            cell = self.get_grid_cell(i, j);
+    pub fn set_back_to_back_flag(&mut self, value: bool) {
+        self.back_to_back = value
+    }
+
+    pub fn set_combo(&mut self, combo: Option<u32>) {
+        self.combo = combo
+    }
+
+    pub fn set_last_clear(&mut self, report: Option<ClearReport>) {
+        self.last_clear = report
+    }
+
+    /// Ends the game, unless the mode forbids topping out : the stack is
+    /// then cleared and the game goes on
+    pub fn set_top_out(&mut self, top_out: TopOut) {
+        if self.mode.can_top_out() {
+            self.top_out = Some(top_out)
+        } else {
+            self.board.clear();
         }
+    }
 
-        self.set_grid_cell(1, j, cell);
-        self.set_grid_cell(0, j, Cell::Empty);
+    pub fn set_goal_reached_flag(&mut self) {
+        self.goal_reached = true
     }
 
-    pub fn add_to_score(&mut self, amount: u32) {
-        self.score += amount
+    pub fn increment_pieces(&mut self) {
+        self.pieces += 1
     }
+}
 
-    pub fn flip_paused_flag(&mut self) {
-        self.paused = !self.paused
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::piece_set;
+    use crate::game::randomizer::RandomizerKind;
+
+    /// Returns the ids of the first pieces dealt to a new game
+    fn deal_pieces(settings: &Settings, pieces: &str) -> Vec<usize> {
+        let mut state = State::new(settings, piece_set::load(pieces).unwrap()).unwrap();
+        let mut pieces = vec![state.get_current_tetromino().get_id()];
+
+        pieces.extend((0..100).map(|_| state.pop_tetromino_queue().get_id()));
+
+        pieces
+    }
+
+    #[test]
+    fn same_seed_deals_same_pieces_for_every_randomizer() {
+        for name in ["7-bag", "14-bag", "history", "nes", "random"] {
+            let settings = Settings {
+                randomizer: RandomizerKind::from_name(name).unwrap(),
+                seed: Some(123456789),
+                ..Settings::default()
+            };
+
+            assert_eq!(
+                deal_pieces(&settings, "tetromino"),
+                deal_pieces(&settings, "tetromino"),
+                "randomizer {name}"
+            );
+        }
+    }
+
+    #[test]
+    fn every_randomizer_deals_every_piece_of_the_set() {
+        for (pieces, count) in [("tetromino", 7), ("pentomino", 12), ("triomino", 2)] {
+            for name in ["7-bag", "14-bag", "history", "nes", "random"] {
+                let settings = Settings {
+                    randomizer: RandomizerKind::from_name(name).unwrap(),
+                    ..Settings::default()
+                };
+                let mut dealt = deal_pieces(&settings, pieces);
+                dealt.sort();
+                dealt.dedup();
+
+                assert_eq!(dealt, (0..count).collect::<Vec<usize>>(), "randomizer {name}, {pieces}");
+            }
+        }
     }
-}
\ No newline at end of file
+}
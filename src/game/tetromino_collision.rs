@@ -1,109 +1,78 @@
-use std::cmp::Ordering;
-
-use crate::game;
+use crate::game::board::Board;
+use crate::game::rotation_system::RotationSystem;
 use crate::game::state::{CurrentTetromino, GridCoords};
 
 impl CurrentTetromino {
 
-    /// Check if some cells of the tetromino is clipping with left or right border
-    ///
-    /// ### Returns :
-    /// - `Ordering::Less` if there is clipping with border left
-    /// - `Ordering::Greater` if there is clipping with border right
-    /// - `Ordering::Equal` if there is no horizontal clipping
-    pub fn is_tetromino_clipping_horizontaly(&self) -> Ordering {
-        let cells_coords = self.get_cells_coords();
-
-        // clipping into left border : check overflow of j
-        if  cells_coords.0.j > 1000000 ||
-            cells_coords.1.j > 1000000 ||
-            cells_coords.2.j > 1000000 ||
-            cells_coords.3.j > 1000000 {
-
-            return Ordering::Less;
-        }
-
-        // clipping into right border
-        if  cells_coords.0.j >= game::GRID_WIDTH ||
-            cells_coords.1.j >= game::GRID_WIDTH ||
-            cells_coords.2.j >= game::GRID_WIDTH ||
-            cells_coords.3.j >= game::GRID_WIDTH {
-
-            return Ordering::Greater;
-        }
+    /// Checks if every cell of the tetromino is inside the board borders
+    pub fn is_inside_board(&self, board: &Board, rotation_system: &RotationSystem) -> bool {
+        let cells_coords = self.get_cells_coords(rotation_system);
 
-        Ordering::Equal
+        cells_coords
+            .iter()
+            .all(|cell_coords| board.contains(*cell_coords))
     }
 
-    /// Checks if some cells of the current tetromino are clipping with
-    /// bottom border of the grid
-    pub fn is_tetromino_clipping_vertically(&self) -> bool {
-        let cells_coords = self.get_cells_coords();
-
-        cells_coords.0.i >= game::GRID_HEIGHT ||
-        cells_coords.1.i >= game::GRID_HEIGHT ||
-        cells_coords.2.i >= game::GRID_HEIGHT ||
-        cells_coords.3.i >= game::GRID_HEIGHT
+    pub fn get_left_collisions_cell_indexes(
+        &self,
+        board: &Board,
+        rotation_system: &RotationSystem
+    ) -> Vec<Option<usize>> {
+        self.get_collisions_cell_indexes(board, rotation_system, (0, -1))
     }
 
-    pub fn get_left_collisions_cell_indexes(&self) -> Vec<Option<usize>> {
-        self.get_collisions_cell_indexes((0, -1))
+    pub fn get_right_collisions_cell_indexes(
+        &self,
+        board: &Board,
+        rotation_system: &RotationSystem
+    ) -> Vec<Option<usize>> {
+        self.get_collisions_cell_indexes(board, rotation_system, (0, 1))
     }
 
-    pub fn get_right_collisions_cell_indexes(&self) -> Vec<Option<usize>> {
-        self.get_collisions_cell_indexes((0, 1))
+    pub fn get_down_collisions_cell_indexes(
+        &self,
+        board: &Board,
+        rotation_system: &RotationSystem
+    ) -> Vec<Option<usize>> {
+        self.get_collisions_cell_indexes(board, rotation_system, (1, 0))
     }
 
-    pub fn get_down_collisions_cell_indexes(&self) -> Vec<Option<usize>> {
-        self.get_collisions_cell_indexes((1, 0))
-    }
+    fn get_collisions_cell_indexes(
+        &self,
+        board: &Board,
+        rotation_system: &RotationSystem,
+        direction: (i32, i32)
+    ) -> Vec<Option<usize>> {
 
-    fn get_collisions_cell_indexes(&self, direction: (i32, i32)) -> Vec<Option<usize>> {
-        let cells_coords = self.get_cells_coords();
+        let cells_coords = self.get_cells_coords(rotation_system);
         let mut collisions = Vec::new();
-        
-        let possible_neighboor0 = GridCoords {
-            i: ((cells_coords.0.i) as i32 + direction.0) as usize,
-            j: ((cells_coords.0.j) as i32 + direction.1) as usize
-        };
-        self.add_to_collisions_if_valid(&mut collisions, possible_neighboor0);
-
-        let possible_neighboor1 = GridCoords {
-            i: ((cells_coords.1.i) as i32 + direction.0) as usize,
-            j: ((cells_coords.1.j) as i32 + direction.1) as usize
-        };
-        self.add_to_collisions_if_valid(&mut collisions, possible_neighboor1);
 
-        let possible_neighboor2 = GridCoords {
-            i: ((cells_coords.2.i) as i32 + direction.0) as usize,
-            j: ((cells_coords.2.j) as i32 + direction.1) as usize
-        };
-        self.add_to_collisions_if_valid(&mut collisions, possible_neighboor2);
-
-        let possible_neighboor3 = GridCoords {
-            i: ((cells_coords.3.i) as i32 + direction.0) as usize,
-            j: ((cells_coords.3.j) as i32 + direction.1) as usize
-        };
-        self.add_to_collisions_if_valid(&mut collisions, possible_neighboor3);
+        for cell_coords in cells_coords {
+            let possible_neighboor = GridCoords {
+                i: (cell_coords.i as i32 + direction.0) as usize,
+                j: (cell_coords.j as i32 + direction.1) as usize
+            };
+            self.add_to_collisions_if_valid(board, rotation_system, &mut collisions, possible_neighboor);
+        }
 
         collisions
     }
 
     fn add_to_collisions_if_valid(
         &self,
+        board: &Board,
+        rotation_system: &RotationSystem,
         collisions: &mut Vec<Option<usize>>,
         possible_neighboor: GridCoords
     ) {
-        if self.is_cell_part_of_me(&possible_neighboor) {
+        if self.is_cell_part_of_me(&possible_neighboor, rotation_system) {
             return
         }
 
-        if possible_neighboor.i >= game::GRID_HEIGHT
-            || possible_neighboor.j >= game::GRID_WIDTH {
-            
+        if !board.contains(possible_neighboor) {
             collisions.push(None)
         } else {
-            collisions.push(Some(possible_neighboor.to_grid_index()));
+            collisions.push(Some(board.to_index(possible_neighboor)));
         }
     }
 
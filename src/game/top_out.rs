@@ -0,0 +1,38 @@
+use crate::game::cell::Cell;
+use crate::game::state::State;
+
+/// Reason why the game ended
+#[derive(Clone, Copy, PartialEq)]
+pub enum TopOut {
+    /// The new tetromino spawned overlapping cells of the stack
+    BlockOut,
+    /// A tetromino locked entirely above the visible field
+    LockOut,
+    /// Garbage rows pushed the stack into the vanish zone
+    Overflow
+}
+
+impl State {
+    /// Checks if the current tetromino overlaps full cells of the grid,
+    /// which happens when it spawns on top of the stack
+    pub fn is_current_tetromino_blocked_out(&self) -> bool {
+        let cells_coords = self.get_current_tetromino_ref().get_cells_coords(self.get_rotation_system());
+
+        let board = self.get_board();
+
+        cells_coords
+            .iter()
+            .any(|cell_coords| board.get_cell_at(*cell_coords) != Cell::Empty)
+    }
+
+    /// Checks if every cell of the current tetromino is in the vanish zone,
+    /// meaning that locking it here ends the game
+    pub fn is_current_tetromino_locked_out(&self) -> bool {
+        let cells_coords = self.get_current_tetromino_ref().get_cells_coords(self.get_rotation_system());
+        let vanish_zone_height = self.get_board().get_vanish_zone_height();
+
+        cells_coords
+            .iter()
+            .all(|cell_coords| cell_coords.i < vanish_zone_height)
+    }
+}
@@ -1,14 +1,69 @@
-pub const SCORE_COUNTER: usize = 563;
-pub const LEVEL_COUNTER: usize = 770;
-pub const LINES_COUNTER: usize = 977;
-pub const PAUSE_MESSAGE: usize = 1178;
+use crate::game::view::screen;
 
-pub const GRID_ORIGIN: usize = 90;
+// (row, column) of each position, the column being relative to the panel or
+// to the board frame the position is in. Rows of the counters and messages
+// start below the HOLD section.
 
-pub const HOLD_GRID_ORIGIN: usize = 143;
-pub const HOLD_GRID_CENTER: usize = 215;
+const HUD_STAT_LABELS: [(usize, usize); 3] = [(2, 5), (5, 5), (8, 5)];
+const HUD_STAT_VALUES: [(usize, usize); 3] = [(3, 6), (6, 6), (9, 6)];
+const STATUS_MESSAGE: (usize, usize) = (12, 5);
+const CLEAR_MESSAGE: (usize, usize) = (11, 4);
+const BONUS_MESSAGE: (usize, usize) = (13, 4);
 
-pub const NEXT_GRID_ORIGIN: usize = 193;
-pub const NEXT_GRID_CENTER0: usize = 265;
-pub const NEXT_GRID_CENTER1: usize = 472;
-pub const NEXT_GRID_CENTER2: usize = 679;
+const GRID_ORIGIN: (usize, usize) = (1, 1);
+
+const HOLD_GRID_ORIGIN: (usize, usize) = (2, 5);
+
+const NEXT_GRID_FIRST_ORIGIN: (usize, usize) = (2, 4);
+
+/// Screen indexes where the state data is loaded, which depend on the
+/// size of the board
+pub struct CursorPositions {
+    pub hud_stat_labels: [usize; 3],
+    pub hud_stat_values: [usize; 3],
+    pub status_message: usize,
+    pub clear_message: usize,
+    pub bonus_message: usize,
+    pub grid_origin: usize,
+    pub hold_grid_origin: usize,
+    /// One origin per preview, from the next tetromino on
+    pub next_grid_origins: Vec<usize>
+}
+
+impl CursorPositions {
+    /// `screen_width` counts the line break at the end of each line, and
+    /// `preview_size` gives the rows and columns of the HOLD and NEXT boxes
+    pub fn new(
+        screen_width: usize,
+        board_width: usize,
+        previews: usize,
+        preview_size: (usize, usize)
+    ) -> CursorPositions {
+
+        let frame_origin = screen::get_left_panel_width(preview_size);
+        let right_panel_origin = frame_origin + screen::get_board_frame_width(board_width);
+        let hud_origin = screen::get_hold_section_height(preview_size);
+
+        let left_panel = |(row, column): (usize, usize)| row * screen_width + column;
+        let hud = |(row, column): (usize, usize)| left_panel((hud_origin + row, column));
+        let frame = |(row, column): (usize, usize)| row * screen_width + frame_origin + column;
+        let right_panel = |(row, column): (usize, usize)|
+            row * screen_width + right_panel_origin + column;
+
+        CursorPositions {
+            hud_stat_labels: HUD_STAT_LABELS.map(hud),
+            hud_stat_values: HUD_STAT_VALUES.map(hud),
+            status_message: hud(STATUS_MESSAGE),
+            clear_message: hud(CLEAR_MESSAGE),
+            bonus_message: hud(BONUS_MESSAGE),
+            grid_origin: frame(GRID_ORIGIN),
+            hold_grid_origin: left_panel(HOLD_GRID_ORIGIN),
+            next_grid_origins: (0..previews)
+                .map(|k| right_panel((
+                    NEXT_GRID_FIRST_ORIGIN.0 + k * screen::get_next_preview_height(preview_size),
+                    NEXT_GRID_FIRST_ORIGIN.1
+                )))
+                .collect()
+        }
+    }
+}
@@ -1,29 +1,40 @@
 use std::time::Duration;
 use crossterm::event::{self, Event, KeyCode, KeyEventKind};
 
-use crate::game::game_action::GameAction;
+use crate::game::game_action::{GameAction, InputEvent};
 
-pub fn read() -> Option<GameAction> {
-    if let Ok(is_event) = event::poll(Duration::from_millis(0)) {
-        if is_event {
-            if let Ok(Event::Key(key_event)) = event::read() {
-                if key_event.kind == KeyEventKind::Press {
-                    
-                    return match key_event.code {
-                        KeyCode::Left => Some(GameAction::Left),
-                        KeyCode::Right => Some(GameAction::Right),
-                        KeyCode::Down => Some(GameAction::Down),
-                        KeyCode::Char('r') => Some(GameAction::Rotate),
-                        KeyCode::Char('s') => Some(GameAction::Store),
-                        KeyCode::Char('p') => Some(GameAction::Pause),
-                        KeyCode::Char('q') => Some(GameAction::Quit),
-                        _ => None
-                    }
-                }
+/// Returns the next pending key event, if any. Repeats sent by the
+/// terminal for held keys are skipped when releases are reported, since
+/// the engine repeats held keys itself.
+pub fn read() -> Option<InputEvent> {
+    while let Ok(true) = event::poll(Duration::from_millis(0)) {
+        if let Ok(Event::Key(key_event)) = event::read()
+            && let Some(action) = get_key_action(key_event.code) {
+
+            match key_event.kind {
+                KeyEventKind::Press => return Some(InputEvent::Pressed(action)),
+                KeyEventKind::Release => return Some(InputEvent::Released(action)),
+                KeyEventKind::Repeat => ()
             }
         }
-
     }
 
     None
-}
\ No newline at end of file
+}
+
+fn get_key_action(key_code: KeyCode) -> Option<GameAction> {
+    match key_code {
+        KeyCode::Left => Some(GameAction::Left),
+        KeyCode::Right => Some(GameAction::Right),
+        KeyCode::Down => Some(GameAction::Down),
+        KeyCode::Char(' ') => Some(GameAction::HardDrop),
+        KeyCode::Up | KeyCode::Char('r') => Some(GameAction::RotateClockwise),
+        KeyCode::Char('e') | KeyCode::Char('z') => Some(GameAction::RotateCounterclockwise),
+        KeyCode::Char('a') => Some(GameAction::Rotate180),
+        KeyCode::Char('s') => Some(GameAction::Store),
+        KeyCode::Char('g') => Some(GameAction::ToggleGhost),
+        KeyCode::Char('p') => Some(GameAction::Pause),
+        KeyCode::Char('q') => Some(GameAction::Quit),
+        _ => None
+    }
+}
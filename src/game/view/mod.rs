@@ -7,61 +7,144 @@ mod screen;
 
 use std::io::{self, Write};
 use crossterm::{
+    event::{KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags},
     terminal,
     cursor,
-    style::Print,
+    style::{Colors, Print, ResetColor, SetColors},
     queue,
     execute
 };
 
+use crate::game::hold::HoldRule;
 use crate::game::state::State;
-
-const SCREEN_WIDTH : usize = 69;
-const SCREEN_HEIGHT: usize = 22;
-const SCREEN_LENGTH: usize = SCREEN_WIDTH * SCREEN_HEIGHT - 2;
+use crate::game::view::cursor_positions::CursorPositions;
 
 const CELL_WIDTH: usize = 3;
-const SECTION_WIDTH: usize = 4 * CELL_WIDTH;
-const HOLD_SECTION_HEIGHT: usize = 2;
-const NEXT_SECTION_HEIGHT: usize = 8;
 
 pub struct View {
-    pub vram: [u8; SCREEN_LENGTH],
+    pub vram: Vec<u8>,
+    /// Colors of each character of the VRAM, None for those of the terminal
+    colors: Vec<Option<Colors>>,
+    /// Length of a screen line, line break included
+    screen_width: usize,
+    cursor_positions: CursorPositions,
+    /// Blocks drawn per board cell in each direction
+    block_size: usize,
+    /// Rows and columns of the HOLD and NEXT boxes
+    preview_size: (usize, usize),
     stdout: io::Stdout,
-    
+    ghost_enabled: bool,
+    keyboard_enhanced: bool
+}
+
+impl View {
+    pub fn is_ghost_enabled(&self) -> bool {
+        self.ghost_enabled
+    }
+
+    pub fn flip_ghost_flag(&mut self) {
+        self.ghost_enabled = !self.ghost_enabled
+    }
+
+    /// Returns true if the terminal reports key releases, which lets the
+    /// engine repeat held keys itself
+    pub fn has_key_release_events(&self) -> bool {
+        self.keyboard_enhanced || cfg!(windows)
+    }
 }
 
-pub fn initialize_view() -> io::Result<View> {
+/// Sets up the terminal and lays the screen out around the board, with the
+/// HOLD and NEXT sections the rules need
+pub fn initialize_view(state: &State) -> io::Result<View> {
     terminal::enable_raw_mode()?;
 
     let mut stdout = io::stdout();
     execute!(stdout, cursor::Hide)?;
 
+    // Windows consoles always report key releases, other terminals only
+    // when they support the kitty keyboard protocol
+    let keyboard_enhanced = !cfg!(windows)
+        && terminal::supports_keyboard_enhancement().unwrap_or(false)
+        && execute!(
+            stdout,
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+        ).is_ok();
+
+    let board = state.get_board();
+    let block_size = state.get_block_size();
+    let columns = board.get_width() * block_size;
+    let previews = state.get_next_tetrominos_queue_length();
+    let preview_size = state.get_piece_set().get_preview_size();
+    let screen_lines = screen::generate_lines(
+        columns,
+        board.get_visible_height() * block_size,
+        previews,
+        preview_size,
+        state.get_hold_rule() != HoldRule::Disabled
+    );
+    let screen_width = screen_lines[0].len() + 2;
+    let vram = vram::initialize(&screen_lines);
+
     Ok(
         View {
-            vram: vram::initialize(),
+            colors: vec![None; vram.len()],
+            vram,
+            screen_width,
+            cursor_positions: CursorPositions::new(screen_width, columns, previews, preview_size),
+            block_size,
+            preview_size,
             stdout,
+            ghost_enabled: true,
+            keyboard_enhanced
     })
 }
 
 pub fn display_state(state: &State, view: &mut View) -> io::Result<()> {
-    let vram_str = match String::from_utf8(view.vram.to_vec()) {
-        Ok(vram_str) => vram_str,
-        Err(e) => panic!("UTF8 error in display_state : {}", e.to_string()),
-    };
+    let vram = view.vram.to_vec();
+    let colors = view.colors.to_vec();
 
     vram::load_state_data(state, view);
 
     queue!(view.stdout, terminal::Clear(terminal::ClearType::All))?;
     queue!(view.stdout, terminal::Clear(terminal::ClearType::Purge))?;
     queue!(view.stdout, cursor::MoveTo(0, 0))?;
-    queue!(view.stdout, Print(vram_str))?;
+    print_vram(view, &vram, &colors)?;
     view.stdout.flush()?;
 
     Ok(())
 }
 
+/// Prints the VRAM in runs of characters sharing the same colors
+fn print_vram(view: &mut View, vram: &[u8], colors: &[Option<Colors>]) -> io::Result<()> {
+    let mut run_start = 0;
+
+    while run_start < vram.len() {
+        let run_colors = colors[run_start];
+        let run_end = (run_start..vram.len())
+            .find(|&index| colors[index] != run_colors)
+            .unwrap_or(vram.len());
+
+        let run_str = match String::from_utf8(vram[run_start..run_end].to_vec()) {
+            Ok(run_str) => run_str,
+            Err(e) => panic!("UTF8 error in display_state : {}", e),
+        };
+
+        match run_colors {
+            Some(run_colors) => queue!(view.stdout, SetColors(run_colors), Print(run_str), ResetColor)?,
+            None => queue!(view.stdout, Print(run_str))?
+        }
+
+        run_start = run_end;
+    }
+
+    Ok(())
+}
+
 pub fn close_view(view: &mut View) -> io::Result<()> {
+    if view.keyboard_enhanced {
+        execute!(view.stdout, PopKeyboardEnhancementFlags)?;
+    }
+
     execute!(view.stdout, cursor::Show)?;
     terminal::disable_raw_mode()?;
     Ok(())
@@ -1,23 +1,186 @@
-pub const SCREEN_STR: &str =
-"        HOLD        -------------------------------       NEXT     
-    -------------   |  |  |  |  |  |  |  |  |  |  |   -------------
-    |  |  |  |  |   |  |  |  |  |  |  |  |  |  |  |   |  |  |  |  |
-    |  |  |  |  |   |  |  |  |  |  |  |  |  |  |  |   |  |  |  |  |
-    -------------   |  |  |  |  |  |  |  |  |  |  |   -------------
-                    |  |  |  |  |  |  |  |  |  |  |   |  |  |  |  |
-    ------------    |  |  |  |  |  |  |  |  |  |  |   |  |  |  |  |
-    |  SCORE   |    |  |  |  |  |  |  |  |  |  |  |   -------------
-    |          |    |  |  |  |  |  |  |  |  |  |  |   |  |  |  |  |
-    ------------    |  |  |  |  |  |  |  |  |  |  |   |  |  |  |  |
-    |  LEVEL   |    |  |  |  |  |  |  |  |  |  |  |   -------------
-    |          |    |  |  |  |  |  |  |  |  |  |  |                
-    ------------    |  |  |  |  |  |  |  |  |  |  |     CONTROLS   
-    |  LINES   |    |  |  |  |  |  |  |  |  |  |  |   -------------
-    |          |    |  |  |  |  |  |  |  |  |  |  |   |S to store |
-    ------------    |  |  |  |  |  |  |  |  |  |  |   |R to rotate|
-                    |  |  |  |  |  |  |  |  |  |  |   |P to pause |
-                    |  |  |  |  |  |  |  |  |  |  |   |Q to quit  |
-                    |  |  |  |  |  |  |  |  |  |  |   -------------
--------------       |  |  |  |  |  |  |  |  |  |  |                
- Created by |       |  |  |  |  |  |  |  |  |  |  |     -----------
- Alexis Bon |       -------------------------------     |  v1.1.0  ";
+use crate::game::view;
+
+/// Width of the panel on the left of the board, with HOLD and the counters,
+/// unless the HOLD box needs more
+const LEFT_PANEL_MIN_WIDTH: usize = 20;
+
+// Side panels are split in a top part, aligned with the top of the board,
+// and a bottom part, aligned with the bottom of the screen
+
+// The left panel starts with the HOLD section, replaced with blanks when
+// hold is disabled so that the counters stay in place. The labels of the
+// counters depend on the mode. The HOLD and NEXT boxes are as large as the
+// pieces of the loaded set need.
+
+/// Columns of the panels before their HOLD and NEXT boxes
+const HOLD_BOX_INDENT: usize = 4;
+const NEXT_BOX_INDENT: usize = 3;
+
+const LEFT_PANEL_TOP: [&str; 14] = [
+    "                    ",
+    "    ------------    ",
+    "    |          |    ",
+    "    |          |    ",
+    "    ------------    ",
+    "    |          |    ",
+    "    |          |    ",
+    "    ------------    ",
+    "    |          |    ",
+    "    |          |    ",
+    "    ------------    ",
+    "                    ",
+    "                    ",
+    "                    "
+];
+
+const LEFT_PANEL_BOTTOM: [&str; 3] = [
+    "-------------       ",
+    " Created by |       ",
+    " Alexis Bon |       "
+];
+
+// The right panel starts with a NEXT header followed by one preview box per
+// tetromino of the next queue, then shows the controls
+
+const CONTROLS: [&str; 8] = [
+    "                         ",
+    "          CONTROLS       ",
+    "   ----------------------",
+    "   | R cw  E ccw  A 180 |",
+    "   | Space    hard drop |",
+    "   | S store    G ghost |",
+    "   | P pause    Q quit  |",
+    "   ----------------------"
+];
+
+const RIGHT_PANEL_BOTTOM: [&str; 3] = [
+    "                ",
+    "     -----------",
+    "     |  v1.1.0  "
+];
+
+/// Returns the width of the panel on the left of the board, for HOLD and
+/// NEXT boxes of `preview_size` (rows, columns)
+pub fn get_left_panel_width(preview_size: (usize, usize)) -> usize {
+    let hold_box_width = preview_size.1 * view::CELL_WIDTH + 1;
+
+    LEFT_PANEL_MIN_WIDTH.max(HOLD_BOX_INDENT + hold_box_width + 3)
+}
+
+/// Returns the lines of the HOLD section above the counters, for boxes of
+/// `preview_size` (rows, columns)
+pub fn get_hold_section_height(preview_size: (usize, usize)) -> usize {
+    preview_size.0 + 3
+}
+
+/// Returns the lines of a preview box in the NEXT section, bottom border
+/// included
+pub fn get_next_preview_height(preview_size: (usize, usize)) -> usize {
+    preview_size.0 + 1
+}
+
+/// Returns the width of the frame drawn around a board of `board_width`
+/// columns
+pub fn get_board_frame_width(board_width: usize) -> usize {
+    board_width * view::CELL_WIDTH + 1
+}
+
+/// Generates the lines of the screen : the frame of the board between the
+/// two side panels, as high as the highest of them. The NEXT section is
+/// hidden when there is no preview.
+pub fn generate_lines(
+    board_width: usize,
+    board_visible_height: usize,
+    previews: usize,
+    preview_size: (usize, usize),
+    hold_shown: bool
+) -> Vec<String> {
+
+    let (box_rows, box_columns) = preview_size;
+    let box_width = box_columns * view::CELL_WIDTH + 1;
+    let box_border = "-".repeat(box_width);
+    let box_cells = "|  ".repeat(box_columns) + "|";
+
+    let hold_indent = " ".repeat(HOLD_BOX_INDENT);
+    let mut hold_section = vec![
+        format!("{hold_indent}{:^box_width$}", "HOLD"),
+        format!("{hold_indent}{box_border}")
+    ];
+    hold_section.extend(vec![format!("{hold_indent}{box_cells}"); box_rows]);
+    hold_section.push(format!("{hold_indent}{box_border}"));
+
+    let mut left_panel_top: Vec<String> = if hold_shown {
+        hold_section
+    } else {
+        vec![String::new(); hold_section.len()]
+    };
+
+    left_panel_top.extend(LEFT_PANEL_TOP.map(String::from));
+
+    let next_indent = " ".repeat(NEXT_BOX_INDENT);
+    let mut right_panel_top = Vec::new();
+
+    if previews > 0 {
+        right_panel_top.push(format!("{next_indent}{:^box_width$}", "NEXT"));
+        right_panel_top.push(format!("{next_indent}{box_border}"));
+
+        for _ in 0..previews {
+            right_panel_top.extend(vec![format!("{next_indent}{box_cells}"); box_rows]);
+            right_panel_top.push(format!("{next_indent}{box_border}"));
+        }
+    }
+
+    right_panel_top.extend(CONTROLS.map(String::from));
+
+    let left_panel_bottom = LEFT_PANEL_BOTTOM.map(String::from);
+    let right_panel_bottom = RIGHT_PANEL_BOTTOM.map(String::from);
+    let right_panel_width = right_panel_top
+        .iter()
+        .chain(right_panel_bottom.iter())
+        .map(|line| line.len())
+        .max()
+        .unwrap_or(0);
+
+    let frame_width = get_board_frame_width(board_width);
+    let screen_height = (left_panel_top.len() + LEFT_PANEL_BOTTOM.len())
+        .max(right_panel_top.len() + RIGHT_PANEL_BOTTOM.len())
+        .max(board_visible_height + 2);
+
+    let border_line = "-".repeat(frame_width);
+    let cells_line = "|  ".repeat(board_width) + "|";
+    let blank_line = " ".repeat(frame_width);
+
+    (0..screen_height)
+        .map(|k| {
+            let frame_part = if k == 0 || k == board_visible_height + 1 {
+                &border_line
+            } else if k <= board_visible_height {
+                &cells_line
+            } else {
+                &blank_line
+            };
+
+            format!(
+                "{:<left_width$}{}{:<right_panel_width$}",
+                get_panel_line(&left_panel_top, &left_panel_bottom, k, screen_height),
+                frame_part,
+                get_panel_line(&right_panel_top, &right_panel_bottom, k, screen_height),
+                left_width = get_left_panel_width(preview_size)
+            )
+        })
+        .collect()
+}
+
+/// Returns line `k` of a side panel, leaving the gap between its top and
+/// bottom parts empty
+fn get_panel_line<'a>(top: &'a [String], bottom: &'a [String], k: usize, screen_height: usize) -> &'a str {
+    let bottom_origin = screen_height - bottom.len();
+
+    if k < top.len() {
+        &top[k]
+    } else if k >= bottom_origin {
+        &bottom[k - bottom_origin]
+    } else {
+        ""
+    }
+}
@@ -1,35 +1,68 @@
-use crate::game::state::GridCoords;
-use crate::game::{state::CurrentTetromino, tetromino::Tetromino};
-use crate::game::view::{self, cursor_positions};
+use crossterm::style::Color;
+
+use crate::game::board::Board;
+use crate::game::state::{GridCoords, State};
+use crate::game::{piece_set, state::CurrentTetromino, tetromino::Tetromino};
+use crate::game::view::{self, View};
 
 pub struct TetrominoSprite {
-    pub cells_screen_position: (Option<usize>, Option<usize>, Option<usize>, Option<usize>)
+    /// Screen position of each cell, None for the hidden ones
+    pub cells_screen_position: Vec<Option<usize>>,
+    /// Blocks drawn per cell in each direction, from the screen position of
+    /// the cell
+    pub block_size: usize,
+    pub color: Color
 }
 
-const SCREEN_WIDTH_I32: i32 = view::SCREEN_WIDTH as i32;
 const CELL_WIDTH_I32  : i32 = view::CELL_WIDTH as i32;
 
 impl TetrominoSprite {
-    pub fn display_sprite(tetromino: Tetromino, center_screen_position: usize)
+    /// Returns the sprite of the tetromino centered in a HOLD or NEXT box
+    /// whose first cell is at `box_origin`
+    pub fn display_sprite(tetromino: Tetromino, box_origin: usize, view: &View, state: &State)
         -> TetrominoSprite {
-        
+
+        let shape = state.get_rotation_system().get_shape(tetromino, 0);
+        let (height, width) = piece_set::get_bounding_size(shape);
+        let (box_rows, box_columns) = view.preview_size;
+
+        // Shapes are pushed to the bottom and to the left when they cannot
+        // be centered exactly
+        let top = shape.iter().map(|offset| offset.0).min().unwrap_or(0);
+        let left = shape.iter().map(|offset| offset.1).min().unwrap_or(0);
+        let box_center = (
+            (box_rows - height).div_ceil(2) as i32 - top,
+            ((box_columns - width) / 2) as i32 - left
+        );
+        let center_screen_position = box_origin as i32
+            + box_center.0 * view.screen_width as i32
+            + box_center.1 * CELL_WIDTH_I32;
+
         TetrominoSprite {
             cells_screen_position: Self::get_screen_cells_from_shape(
                     center_screen_position,
-                    tetromino.get_display_shape()
-                )
+                    shape,
+                    view.screen_width
+                ),
+            block_size: 1,
+            color: state.get_piece_set().get_definition(tetromino).color
         }
     }
 
     pub fn of_current_tetromino(
-        current_tetromino: &CurrentTetromino
+        current_tetromino: &CurrentTetromino,
+        view: &View,
+        state: &State
     ) -> TetrominoSprite {
 
         TetrominoSprite {
-            cells_screen_position: Self::get_screen_cells_from_shape(
-                current_tetromino.get_position().to_screen_index(),
-                current_tetromino.get_shape()
-            )
+            cells_screen_position: current_tetromino
+                .get_cells_coords(state.get_rotation_system())
+                .iter()
+                .map(|cell_coords| cell_coords.to_screen_index(view, state.get_board()))
+                .collect(),
+            block_size: view.block_size,
+            color: state.get_piece_set().get_definition(current_tetromino.get_tetromino()).color
         }
     }
 
@@ -49,31 +82,33 @@ impl TetrominoSprite {
     //     )
     // }
 
-    fn get_screen_cells_from_shape(screen_center: usize, shape: [(i32, i32); 3])
-        -> (Option<usize>, Option<usize>, Option<usize>, Option<usize>) {
+    fn get_screen_cells_from_shape(screen_center: i32, shape: &[(i32, i32)], screen_width: usize)
+        -> Vec<Option<usize>> {
 
-        let screen_center_i32 = screen_center as i32;
+        let screen_width_i32 = screen_width as i32;
 
-        let cell0_screen_index_i32 =
-            screen_center_i32 + shape[0].0 * SCREEN_WIDTH_I32 + shape[0].1 * CELL_WIDTH_I32;
-        let cell1_screen_index_i32 =
-            screen_center_i32 + shape[1].0 * SCREEN_WIDTH_I32 + shape[1].1 * CELL_WIDTH_I32;
-        let cell2_screen_index_i32 =
-            screen_center_i32 + shape[2].0 * SCREEN_WIDTH_I32 + shape[2].1 * CELL_WIDTH_I32;
+        shape
+            .iter()
+            .map(|offset| {
+                let cell_screen_index_i32 =
+                    screen_center + offset.0 * screen_width_i32 + offset.1 * CELL_WIDTH_I32;
 
-        (
-            Some(screen_center),
-            if cell0_screen_index_i32 >= 0 {Some(cell0_screen_index_i32 as usize)} else {None},
-            if cell1_screen_index_i32 >= 0 {Some(cell1_screen_index_i32 as usize)} else {None},
-            if cell2_screen_index_i32 >= 0 {Some(cell2_screen_index_i32 as usize)} else {None}
-        )
+                if cell_screen_index_i32 >= 0 {Some(cell_screen_index_i32 as usize)} else {None}
+            })
+            .collect()
     }
 }
 
 impl GridCoords {
-    pub fn to_screen_index(&self) -> usize {
-        cursor_positions::GRID_ORIGIN
-        + self.i * view::SCREEN_WIDTH
-        + self.j * view::CELL_WIDTH
+    /// Returns the screen index of the top left block of the cell, or None
+    /// for cells in the vanish zone, which is not displayed
+    pub fn to_screen_index(self, view: &View, board: &Board) -> Option<usize> {
+        let visible_i = self.i.checked_sub(board.get_vanish_zone_height())?;
+
+        Some(
+            view.cursor_positions.grid_origin
+            + visible_i * view.block_size * view.screen_width
+            + self.j * view.block_size * view::CELL_WIDTH
+        )
     }
 }
\ No newline at end of file
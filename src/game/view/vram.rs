@@ -1,82 +1,132 @@
-use std::ptr;
+use crossterm::style::{Color, Colors};
 
-use crate::game;
-use crate::game::state::{State, CurrentTetromino};
+use crate::game::piece_set::PieceSet;
+use crate::game::state::State;
 use crate::game::cell::Cell;
-use crate::game::view::screen;
-use crate::game::view::{self, View, cursor_positions, tetromino_sprite::TetrominoSprite};
+use crate::game::hold::HoldRule;
+use crate::game::view::{self, View, screen, tetromino_sprite::TetrominoSprite};
 
 const TETROMINO_CELL_CHAR: u8 = b'H';
 const EMPTY_CELL_CHAR: u8 = b' ';
+const TETROMINO_CELL_CHARS: [u8; 2] = [TETROMINO_CELL_CHAR, TETROMINO_CELL_CHAR];
+const GHOST_CELL_CHARS: [u8; 2] = [b'[', b']'];
+const GARBAGE_COLOR: Color = Color::DarkGrey;
 
-pub fn initialize() -> [u8; view::SCREEN_LENGTH] {
-    let content_string = screen::SCREEN_STR.replace("\n", "\n\r");
-    let content_bytes = content_string.as_bytes();
+const STATUS_MESSAGE_LENGTH: usize = 11;
+const CLEAR_MESSAGE_LENGTH: usize = 15;
+const HUD_STAT_LABEL_LENGTH: usize = 10;
+const HUD_STAT_VALUE_LENGTH: usize = 7;
+const PAUSE_MESSAGE: &[u8] = b"GAME PAUSED";
+const GAME_OVER_MESSAGE: &[u8] = b" GAME OVER";
 
-    let mut vram = [b' '; view::SCREEN_LENGTH];
-    unsafe {
-        ptr::copy(
-            content_bytes.as_ptr(),
-            vram.as_mut_ptr(),
-            view::SCREEN_LENGTH
-        );
-    }
+pub fn initialize(screen_lines: &[String]) -> Vec<u8> {
+    screen_lines.join("\n\r").into_bytes()
+}
 
-    vram
+/// Returns the colors of a block filled with `color`, which hides its
+/// characters on color terminals
+fn get_solid_colors(color: Color) -> Colors {
+    Colors::new(color, color)
 }
 
 pub fn load_state_data(state: &State, view: &mut View) {
     load_grid(state, view);
-    load_current_tetromino_sprite(view, state.get_current_tetromino_ref());
+    if state.is_tetromino_falling() {
+        load_ghost_sprite_if_needed(view, state);
+        load_current_tetromino_sprite(view, state);
+    }
     load_hold_section(view, state);
     load_next_section(view, state);
-    load_uint(view, state.get_score(), cursor_positions::SCORE_COUNTER);
-    load_uint(view, state.get_level(), cursor_positions::LEVEL_COUNTER);
-    load_uint(view, state.get_lines(), cursor_positions::LINES_COUNTER);
-    load_pause_message_if_needed(view, state);
+    load_hud_stats(view, state);
+    load_status_message(view, state);
+    load_clear_messages(view, state);
 }
 
+/// Loads the visible rows of the grid, the vanish zone above stays hidden.
+/// Locked cells the stack visibility hides are drawn empty until the game
+/// is over.
 fn load_grid(state: &State, view: &mut View) {
-    for (index, cell) in state.grid.iter().enumerate() {
+    let board = state.get_board();
+    let visible_cells_origin = board.get_vanish_zone_height() * board.get_width();
+    let stack_visibility = state.get_stack_visibility();
+
+    for (index, cell) in board.get_cells().iter().enumerate().skip(visible_cells_origin) {
         let cell_grid_position = (
-            index / game::GRID_WIDTH,
-            index % game::GRID_WIDTH
+            index / board.get_width() - board.get_vanish_zone_height(),
+            index % board.get_width()
         );
+        let shown = state.is_game_over()
+            || stack_visibility.is_cell_shown(board.get_lock_clocks()[index], state.get_clock());
 
-        load_tetromino_cell_grid(view, cell, cell_grid_position);
+        load_tetromino_cell_grid(
+            view,
+            state.get_piece_set(),
+            if shown { cell } else { &Cell::Empty },
+            cell_grid_position
+        );
     }
 }
 
-fn load_tetromino_cell_grid(view: &mut View, cell: &Cell, cell_grid_position: (usize, usize)) {
-    let cell_char = match cell {
-        Cell::Full => TETROMINO_CELL_CHAR,
-        Cell::Empty => EMPTY_CELL_CHAR,
+/// Loads a cell of the grid as its blocks, 2x2 of them in big mode
+fn load_tetromino_cell_grid(
+    view: &mut View,
+    piece_set: &PieceSet,
+    cell: &Cell,
+    cell_grid_position: (usize, usize)
+) {
+    let (cell_char, cell_colors) = match cell {
+        Cell::Full(tetromino) => {
+            let color = piece_set.get_definition(*tetromino).color;
+
+            (TETROMINO_CELL_CHAR, Some(get_solid_colors(color)))
+        },
+        Cell::Garbage => (TETROMINO_CELL_CHAR, Some(get_solid_colors(GARBAGE_COLOR))),
+        Cell::Empty => (EMPTY_CELL_CHAR, None)
     };
-    let cell_screen_position = cursor_positions::GRID_ORIGIN
-        + cell_grid_position.0 * view::SCREEN_WIDTH
-        + cell_grid_position.1 * view::CELL_WIDTH;
+    let cell_screen_position = view.cursor_positions.grid_origin
+        + cell_grid_position.0 * view.block_size * view.screen_width
+        + cell_grid_position.1 * view.block_size * view::CELL_WIDTH;
+
+    load_block(view, cell_screen_position, view.block_size, [cell_char, cell_char], cell_colors);
+}
+
+fn load_ghost_sprite_if_needed(view: &mut View, state: &State) {
+    if view.is_ghost_enabled() {
+        let sprite = TetrominoSprite::of_current_tetromino(
+            &state.get_ghost_tetromino(),
+            view,
+            state
+        );
+
+        let ghost_colors = Colors { foreground: Some(sprite.color), background: None };
 
-    view.vram[cell_screen_position] = cell_char;
-    view.vram[cell_screen_position + 1] = cell_char;
+        load_sprite(view, sprite, GHOST_CELL_CHARS, ghost_colors);
+    }
 }
 
-fn load_current_tetromino_sprite(view: &mut View, current_tetromino: &CurrentTetromino) {
-    let sprite = TetrominoSprite::of_current_tetromino(&current_tetromino);
+fn load_current_tetromino_sprite(view: &mut View, state: &State) {
+    let sprite = TetrominoSprite::of_current_tetromino(state.get_current_tetromino_ref(), view, state);
 
     load_tetromino_sprite(view, sprite);
 }
 
 fn load_hold_section(view: &mut View, state: &State) {
+    if state.get_hold_rule() == HoldRule::Disabled {
+        return;
+    }
+
     clear_section(
         view,
-        cursor_positions::HOLD_GRID_ORIGIN,
-        view::HOLD_SECTION_HEIGHT
+        view.cursor_positions.hold_grid_origin,
+        view.preview_size.0
     );
 
     if let Some(tetromino) = state.get_stored_tetromino() {
         let tetromino_sprite = TetrominoSprite::display_sprite(
             tetromino,
-            cursor_positions::HOLD_GRID_CENTER
+            view.cursor_positions.hold_grid_origin,
+            view,
+            state
         );
         load_tetromino_sprite(
             view,
@@ -86,22 +136,31 @@ fn load_hold_section(view: &mut View, state: &State) {
 }
 
 fn load_next_section(view: &mut View, state: &State) {
+    let previews = state.get_next_tetrominos_queue_length();
+
+    if previews == 0 {
+        return;
+    }
+
     clear_section(
         view,
-        cursor_positions::NEXT_GRID_ORIGIN,
-        view::NEXT_SECTION_HEIGHT
+        view.cursor_positions.next_grid_origins[0],
+        previews * screen::get_next_preview_height(view.preview_size) - 1
     );
 
-    load_next_section_part(view, state, 0, cursor_positions::NEXT_GRID_CENTER0);
-    load_next_section_part(view, state, 1, cursor_positions::NEXT_GRID_CENTER1);
-    load_next_section_part(view, state, 2, cursor_positions::NEXT_GRID_CENTER2);
+    for queue_index in 0..previews {
+        let box_origin = view.cursor_positions.next_grid_origins[queue_index];
+        load_next_section_part(view, state, queue_index, box_origin);
+    }
 }
 
-fn load_next_section_part(view: &mut View, state: &State, queue_index: usize, screen_center: usize) {
+fn load_next_section_part(view: &mut View, state: &State, queue_index: usize, box_origin: usize) {
     let tetromino = state.get_in_next_tetromino_queue(queue_index);
     let tetromino_sprite = TetrominoSprite::display_sprite(
         tetromino,
-        screen_center
+        box_origin,
+        view,
+        state
     );
     load_tetromino_sprite(
         view,
@@ -110,86 +169,131 @@ fn load_next_section_part(view: &mut View, state: &State, queue_index: usize, sc
 }
 
 fn load_tetromino_sprite(view: &mut View, sprite: TetrominoSprite) {
-    if let Some(cell0_screen_position) = sprite.cells_screen_position.0 {
-        load_tetromino_cell(view, cell0_screen_position);
-    }
-    if let Some(cell1_screen_position) = sprite.cells_screen_position.1 {
-        load_tetromino_cell(view, cell1_screen_position);
-    }
-    if let Some(cell2_screen_position) = sprite.cells_screen_position.2 {
-        load_tetromino_cell(view, cell2_screen_position);
-    }
-    if let Some(cell3_screen_position) = sprite.cells_screen_position.3 {
-        load_tetromino_cell(view, cell3_screen_position);
+    let colors = get_solid_colors(sprite.color);
+
+    load_sprite(view, sprite, TETROMINO_CELL_CHARS, colors);
+}
+
+fn load_sprite(view: &mut View, sprite: TetrominoSprite, cell_chars: [u8; 2], colors: Colors) {
+    for cell_screen_position in sprite.cells_screen_position.into_iter().flatten() {
+        load_block(view, cell_screen_position, sprite.block_size, cell_chars, Some(colors));
     }
 }
 
-fn load_tetromino_cell(view: &mut View, screen_position: usize) {
-    view.vram[screen_position] = TETROMINO_CELL_CHAR;
-    view.vram[screen_position + 1] = TETROMINO_CELL_CHAR;
+/// Loads `block_size` x `block_size` blocks from the top left one, in
+/// `colors` or in the colors of the terminal
+fn load_block(
+    view: &mut View,
+    screen_position: usize,
+    block_size: usize,
+    cell_chars: [u8; 2],
+    colors: Option<Colors>
+) {
+    for di in 0..block_size {
+        for dj in 0..block_size {
+            let block_screen_position = screen_position
+                + di * view.screen_width
+                + dj * view::CELL_WIDTH;
+
+            view.vram[block_screen_position] = cell_chars[0];
+            view.vram[block_screen_position + 1] = cell_chars[1];
+            view.colors[block_screen_position] = colors;
+            view.colors[block_screen_position + 1] = colors;
+        }
+    }
 }
 
 fn clear_section(view: &mut View, origin: usize, height: usize) {
     for k in 0..height {
-        let line_origin = origin + k * view::SCREEN_WIDTH;
+        let line_origin = origin + k * view.screen_width;
         clear_section_line(view, line_origin);
     }
 }
 
 fn clear_section_line(view: &mut View, line_origin: usize) {
-    for index in line_origin..(line_origin + view::SECTION_WIDTH) {
+    for index in line_origin..(line_origin + view.preview_size.1 * view::CELL_WIDTH) {
         if view.vram[index] == TETROMINO_CELL_CHAR {
             view.vram[index] = EMPTY_CELL_CHAR;
+            view.colors[index] = None;
         }
     }
 }
 
-fn load_uint(view: &mut View, n: u32, position: usize) {
-    let digit0 = digit_to_utf8(( n          % 10) as u8);
-    let digit1 = digit_to_utf8(((n / 10   ) % 10) as u8);
-    let digit2 = digit_to_utf8(((n / 100  ) % 10) as u8);
-    let digit3 = digit_to_utf8(((n / 1000 ) % 10) as u8);
-    let digit4 = digit_to_utf8(((n / 10000) % 10) as u8);
+/// Loads the counters chosen by the mode, labels centered and values
+/// aligned to the right. Values too long keep their last characters, as the
+/// original counters kept their last digits.
+fn load_hud_stats(view: &mut View, state: &State) {
+    let stats = state.get_mode().get_hud_stats(state);
 
-    view.vram[position    ] = digit0;
-    view.vram[position - 1] = digit1;
-    view.vram[position - 2] = digit2;
-    view.vram[position - 3] = digit3;
-    view.vram[position - 4] = digit4;
-}
+    for (k, stat) in stats.iter().enumerate() {
+        let label = format!("{:^width$}", stat.label, width = HUD_STAT_LABEL_LENGTH);
+        let value = format!("{:>width$}", stat.value, width = HUD_STAT_VALUE_LENGTH);
+        let value = &value[(value.len() - HUD_STAT_VALUE_LENGTH)..];
 
-fn digit_to_utf8(digit: u8) -> u8 {
-    let utf8_zero = b'0' as u8;
-    utf8_zero + digit
+        let label_origin = view.cursor_positions.hud_stat_labels[k];
+        let value_origin = view.cursor_positions.hud_stat_values[k];
+
+        load_message(view, label_origin, HUD_STAT_LABEL_LENGTH, label.as_bytes());
+        load_message(view, value_origin, HUD_STAT_VALUE_LENGTH, value.as_bytes());
+    }
 }
 
-fn load_pause_message_if_needed(view: &mut View, state: &State) {
-    let message_origin = cursor_positions::PAUSE_MESSAGE;
+fn load_status_message(view: &mut View, state: &State) {
+    let message_origin = view.cursor_positions.status_message;
 
-    if state.is_game_paused() {
-        load_pause_message(view, message_origin);
+    if state.is_goal_reached() {
+        let goal_message = state.get_mode().get_goal_message().as_bytes();
+        load_message(view, message_origin, STATUS_MESSAGE_LENGTH, goal_message);
+    } else if state.is_game_over() {
+        load_message(view, message_origin, STATUS_MESSAGE_LENGTH, GAME_OVER_MESSAGE);
+    } else if state.is_game_paused() {
+        load_message(view, message_origin, STATUS_MESSAGE_LENGTH, PAUSE_MESSAGE);
     } else {
-        clear_pause_message(view, message_origin);
+        clear_message(view, message_origin, STATUS_MESSAGE_LENGTH);
     }
 }
 
-fn load_pause_message(view: &mut View, message_origin: usize) {
-    view.vram[message_origin     ] = b'G';
-    view.vram[message_origin +  1] = b'A';
-    view.vram[message_origin +  2] = b'M';
-    view.vram[message_origin +  3] = b'E';
+fn load_clear_messages(view: &mut View, state: &State) {
+    clear_message(view, view.cursor_positions.clear_message, CLEAR_MESSAGE_LENGTH);
+    clear_message(view, view.cursor_positions.bonus_message, CLEAR_MESSAGE_LENGTH);
+
+    if let Some(report) = state.get_last_clear() {
+        load_message(
+            view,
+            view.cursor_positions.clear_message,
+            CLEAR_MESSAGE_LENGTH,
+            report.get_name().as_bytes()
+        );
+
+        let bonus_message = if report.perfect_clear {
+            String::from("PERFECT CLEAR")
+        } else {
+            match (report.back_to_back, report.combo) {
+                (true, 0) => String::from("BACK-TO-BACK"),
+                (true, combo) => format!("B2B COMBO {combo}"),
+                (false, 0) => String::new(),
+                (false, combo) => format!("COMBO {combo}")
+            }
+        };
 
-    view.vram[message_origin +  5] = b'P';
-    view.vram[message_origin +  6] = b'A';
-    view.vram[message_origin +  7] = b'U';
-    view.vram[message_origin +  8] = b'S';
-    view.vram[message_origin +  9] = b'E';
-    view.vram[message_origin + 10] = b'D';
+        load_message(
+            view,
+            view.cursor_positions.bonus_message,
+            CLEAR_MESSAGE_LENGTH,
+            bonus_message.as_bytes()
+        );
+    }
 }
 
-fn clear_pause_message(view: &mut View, message_origin: usize) {
-    let message_length = 11;
+fn load_message(view: &mut View, message_origin: usize, message_length: usize, message: &[u8]) {
+    clear_message(view, message_origin, message_length);
+
+    let message = &message[..message.len().min(message_length)];
+    view.vram[message_origin..(message_origin + message.len())]
+        .copy_from_slice(message);
+}
 
+fn clear_message(view: &mut View, message_origin: usize, message_length: usize) {
     for i in 0..message_length {
         view.vram[message_origin + i] = b' ';
     }
@@ -2,10 +2,10 @@ mod game;
 
 fn main() {
     println!("Tetris");
-    let result = game::start_game();
+    let result = game::start_game(std::env::args().skip(1));
 
     match result {
         Ok(_) => println!("\n\nBye bye !"),
-        Err(code) => println!("ERROR : Code {code}"),
+        Err(message) => println!("ERROR : {message}"),
     }
 }